@@ -0,0 +1,40 @@
+//passes the versions of the reading dependencies in Cargo.lock to main.rs for --version
+
+use std::path::Path;
+
+//dependencies doing the actual reading, as named in Cargo.toml
+const DEPENDENCIES: [&str; 5] = ["pdf-extract", "zip", "quick-xml", "chrono", "ureq"];
+
+fn main() {
+    let lock = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+    //without a Cargo.lock (e.g. built as a dependency of another crate) the versions are unknown
+    let lock = std::fs::read_to_string(lock).unwrap_or_default();
+    for dependency in DEPENDENCIES {
+        let versions = locked_versions(&lock, dependency);
+        println!(
+            "cargo:rustc-env=TEXT_ANALYSIS_VERSION_{}={}",
+            dependency.replace('-', "_").to_uppercase(),
+            if versions.is_empty() { "unknown".to_string() } else { versions.join(", ") }
+        );
+    }
+}
+
+//versions of `name` in the [[package]] entries of Cargo.lock, more than one if several are locked
+fn locked_versions(lock: &str, name: &str) -> Vec<String> {
+    let name_line = format!("name = \"{}\"", name);
+    let mut lines = lock.lines();
+    let mut versions = Vec::new();
+    while let Some(line) = lines.next() {
+        if line == name_line {
+            if let Some(version) = lines
+                .next()
+                .and_then(|line| line.strip_prefix("version = \""))
+                .and_then(|version| version.strip_suffix('"'))
+            {
+                versions.push(version.to_string());
+            }
+        }
+    }
+    versions
+}
@@ -0,0 +1,358 @@
+//! Command line of the text_analysis binary: the subcommand and its options, parsed from the arguments.
+
+use std::path::PathBuf;
+
+use text_analysis::{
+    CollectOptions, ContextValues, Correction, EmojiPolicy, ExportSort, InvalidUtf8Policy,
+    NewlineKind, TimestampKind, TokenizerKind, TokenizerOptions,
+};
+
+//what to do, chosen by the first argument; without a subcommand the arguments are those of `analyze`
+pub enum Command {
+    //analyze a path and save the results files
+    Analyze(AnalyzeOptions),
+    //analyze a path like `analyze --no-files` and print a JSON report instead of the summary
+    Report(AnalyzeOptions),
+    //combine the word counts of results files of earlier runs into one "word,count" list
+    Merge(MergeOptions),
+    VersionInfo,
+}
+
+//options of `analyze` and `report`, see the usage in main.rs
+pub struct AnalyzeOptions {
+    pub path: String,
+    pub pdf_strict: bool,
+    pub tracked_changes: bool,
+    pub verbose: bool,
+    pub collect_options: CollectOptions,
+    pub context_values: ContextValues,
+    pub timestamp_kind: TimestampKind,
+    pub vocabulary_growth: bool,
+    pub context_window: Option<usize>,
+    pub sentence_lengths: bool,
+    pub light_normalize: bool,
+    pub light_normalize_min_count: Option<u32>,
+    pub exclude_pattern: Option<regex::Regex>,
+    pub invalid_utf8: InvalidUtf8Policy,
+    pub significance: Option<f64>,
+    pub correction: Option<Correction>,
+    pub newline: NewlineKind,
+    pub precision: Option<usize>,
+    pub rarest: Option<usize>,
+    pub no_files: bool,
+    pub quiet: bool,
+    pub drop_suspect: bool,
+    pub log_file: Option<PathBuf>,
+    pub export_sort: ExportSort,
+    pub examples: Option<usize>,
+    pub surface_forms: bool,
+    pub tokenizer_options: TokenizerOptions,
+    pub read_retries: u32,
+    pub reference_freq: Option<PathBuf>,
+    pub reference_total: Option<u64>,
+}
+
+//options of `merge`
+pub struct MergeOptions {
+    pub files: Vec<PathBuf>,
+    //file to save the merged list in; printed if None
+    pub output: Option<PathBuf>,
+    pub export_sort: ExportSort,
+    pub tokenizer_options: TokenizerOptions,
+}
+
+//parse the arguments after the program name
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let first = match args.next() {
+        Some(first) => first,
+        None => return parse_analyze(args),
+    };
+    match first.as_str() {
+        "analyze" => parse_analyze(args),
+        "report" => match parse_analyze(args)? {
+            Command::Analyze(mut options) => {
+                options.no_files = true;
+                Ok(Command::Report(options))
+            }
+            command => Ok(command),
+        },
+        "merge" => parse_merge(args),
+        //`analyze` is the default, so the arguments of earlier versions still work
+        _ => parse_analyze(std::iter::once(first).chain(args)),
+    }
+}
+
+fn parse_analyze(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut pdf_strict = false;
+    let mut tracked_changes = false;
+    let mut verbose = false;
+    let mut collect_options = CollectOptions::default();
+    let mut context_values = ContextValues::Count;
+    let mut timestamp_kind = TimestampKind::LocalSeconds;
+    let mut vocabulary_growth = false;
+    let mut context_window: Option<usize> = None;
+    let mut sentence_lengths = false;
+    let mut light_normalize = false;
+    let mut light_normalize_min_count: Option<u32> = None;
+    let mut exclude_pattern: Option<regex::Regex> = None;
+    let mut invalid_utf8 = InvalidUtf8Policy::Replace;
+    let mut significance: Option<f64> = None;
+    let mut correction: Option<Correction> = None;
+    let mut newline = NewlineKind::Lf;
+    let mut precision: Option<usize> = None;
+    let mut rarest: Option<usize> = None;
+    let mut no_files = false;
+    let mut quiet = false;
+    let mut drop_suspect = false;
+    let mut log_file: Option<PathBuf> = None;
+    let mut export_sort = ExportSort::default();
+    let mut examples: Option<usize> = None;
+    let mut surface_forms = false;
+    let mut tokenizer_options = TokenizerOptions::default();
+    let mut read_retries: u32 = 1;
+    let mut reference_freq: Option<PathBuf> = None;
+    let mut reference_total: Option<u64> = None;
+    let mut path_arg = None;
+    while let Some(arg) = args.next() {
+        if parse_tokenizer_flag(&arg, &mut args, &mut tokenizer_options) {
+            continue;
+        }
+        match arg.as_str() {
+            "--pdf-strict" => pdf_strict = true,
+            "--tracked-changes" => tracked_changes = true,
+            "--verbose" => verbose = true,
+            "--version-info" => return Ok(Command::VersionInfo),
+            "--respect-gitignore" => collect_options.respect_gitignore = true,
+            "--include-previous-outputs" => collect_options.include_previous_outputs = true,
+            "--sentence-lengths" => sentence_lengths = true,
+            "--vocabulary-growth" => vocabulary_growth = true,
+            "--light-normalize" => light_normalize = true,
+            "--light-normalize-min-count" => {
+                light_normalize_min_count = Some(
+                    args.next()
+                        .and_then(|count| count.parse().ok())
+                        .expect("--light-normalize-min-count needs a number"),
+                )
+            }
+            "--exclude-pattern" => {
+                let pattern = args.next().expect("--exclude-pattern needs a regular expression");
+                exclude_pattern = Some(
+                    regex::Regex::new(&pattern)
+                        .map_err(|e| format!("invalid --exclude-pattern {:?}: {}", pattern, e))?,
+                )
+            }
+            "--invalid-utf8" => {
+                invalid_utf8 = match args.next().as_deref() {
+                    Some("fail") => InvalidUtf8Policy::Fail,
+                    Some("replace") => InvalidUtf8Policy::Replace,
+                    Some("skip") => InvalidUtf8Policy::Skip,
+                    _ => panic!("--invalid-utf8 needs \"fail\", \"replace\" or \"skip\""),
+                }
+            }
+            "--read-retries" => {
+                read_retries = args
+                    .next()
+                    .and_then(|retries| retries.parse().ok())
+                    .expect("--read-retries needs a number")
+            }
+            "--context-window" => {
+                context_window = Some(
+                    args.next()
+                        .and_then(|window| window.parse().ok())
+                        .expect("--context-window needs a number"),
+                )
+            }
+            "--context-values" => {
+                context_values = match args.next().as_deref() {
+                    Some("count") => ContextValues::Count,
+                    Some("probability") => ContextValues::Probability,
+                    _ => panic!("--context-values needs \"count\" or \"probability\""),
+                }
+            }
+            "--surface-forms" => surface_forms = true,
+            "--examples" => {
+                examples = Some(
+                    args.next()
+                        .and_then(|k| k.parse().ok())
+                        .expect("--examples needs a number of sentences per word"),
+                )
+            }
+            "--no-files" => no_files = true,
+            "--quiet" => quiet = true,
+            "--drop-suspect" => drop_suspect = true,
+            "--log-file" => {
+                log_file = Some(PathBuf::from(args.next().expect("--log-file needs a path")))
+            }
+            "--sort" => export_sort = parse_sort(args.next()),
+            "--rarest" => {
+                rarest = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .expect("--rarest needs a number of words"),
+                )
+            }
+            "--precision" => {
+                precision = Some(
+                    args.next()
+                        .and_then(|precision| precision.parse().ok())
+                        .expect("--precision needs a number of decimals"),
+                )
+            }
+            "--newline" => {
+                newline = match args.next().as_deref() {
+                    Some("lf") => NewlineKind::Lf,
+                    Some("crlf") => NewlineKind::CrLf,
+                    _ => panic!("--newline needs \"lf\" or \"crlf\""),
+                }
+            }
+            "--timestamp" => {
+                timestamp_kind = match args.next().as_deref() {
+                    Some("local") => TimestampKind::LocalSeconds,
+                    Some("utc-ms") => TimestampKind::UtcMillis,
+                    Some("run-id") => TimestampKind::RunId,
+                    _ => panic!("--timestamp needs \"local\", \"utc-ms\" or \"run-id\""),
+                }
+            }
+            "--reference-freq" => {
+                reference_freq = Some(PathBuf::from(
+                    args.next().expect("--reference-freq needs a file"),
+                ))
+            }
+            "--significance" => {
+                significance = Some(
+                    args.next()
+                        .and_then(|threshold| threshold.parse().ok())
+                        .filter(|threshold: &f64| *threshold > 0.0 && *threshold <= 1.0)
+                        .expect("--significance needs a number in (0, 1] like 0.01"),
+                )
+            }
+            "--correction" => {
+                correction = Some(match args.next().as_deref() {
+                    Some("none") => Correction::None,
+                    Some("bonferroni") => Correction::Bonferroni,
+                    Some("bh") => Correction::BenjaminiHochberg,
+                    _ => panic!("--correction needs \"none\", \"bonferroni\" or \"bh\""),
+                })
+            }
+            "--reference-total" => {
+                reference_total = Some(
+                    args.next()
+                        .and_then(|total| total.parse().ok())
+                        .expect("--reference-total needs a number"),
+                )
+            }
+            _ => path_arg = Some(arg),
+        }
+    }
+    let path = path_arg.expect("no file or directory provided");
+    if light_normalize_min_count.is_some() && !light_normalize {
+        panic!("--light-normalize-min-count needs --light-normalize");
+    }
+    //significance only filters the keyness file
+    if reference_freq.is_none() && (significance.is_some() || correction.is_some()) {
+        panic!("--significance and --correction need --reference-freq");
+    }
+    Ok(Command::Analyze(AnalyzeOptions {
+        path,
+        pdf_strict,
+        tracked_changes,
+        verbose,
+        collect_options,
+        context_values,
+        timestamp_kind,
+        vocabulary_growth,
+        context_window,
+        sentence_lengths,
+        light_normalize,
+        light_normalize_min_count,
+        exclude_pattern,
+        invalid_utf8,
+        significance,
+        correction,
+        newline,
+        precision,
+        rarest,
+        no_files,
+        quiet,
+        drop_suspect,
+        log_file,
+        export_sort,
+        examples,
+        surface_forms,
+        tokenizer_options,
+        read_retries,
+        reference_freq,
+        reference_total,
+    }))
+}
+
+fn parse_merge(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut files = Vec::new();
+    let mut output: Option<PathBuf> = None;
+    let mut export_sort = ExportSort::default();
+    let mut tokenizer_options = TokenizerOptions::default();
+    while let Some(arg) = args.next() {
+        if parse_tokenizer_flag(&arg, &mut args, &mut tokenizer_options) {
+            continue;
+        }
+        match arg.as_str() {
+            "--output" => output = Some(PathBuf::from(args.next().expect("--output needs a file"))),
+            "--sort" => export_sort = parse_sort(args.next()),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        return Err("merge needs at least one results file".to_string());
+    }
+    Ok(Command::Merge(MergeOptions {
+        files,
+        output,
+        export_sort,
+        tokenizer_options,
+    }))
+}
+
+//the flags that choose how text is split into words, shared by all subcommands. Returns false if `arg` is none of them
+fn parse_tokenizer_flag(
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+    options: &mut TokenizerOptions,
+) -> bool {
+    match arg {
+        "--keep-identifiers" => options.keep_identifiers = true,
+        "--turkish-casing" => options.turkish_casing = true,
+        "--collapse-repeats" => {
+            options.collapse_repeats = Some(
+                args.next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--collapse-repeats needs a number of letters"),
+            )
+        }
+        "--tokenizer" => {
+            options.kind = match args.next().as_deref() {
+                Some("whitespace") => TokenizerKind::Whitespace,
+                Some("unicode-words") => TokenizerKind::UnicodeWords,
+                _ => panic!("--tokenizer needs \"whitespace\" or \"unicode-words\""),
+            }
+        }
+        "--emoji" => {
+            options.emoji = Some(match args.next().as_deref() {
+                Some("keep") => EmojiPolicy::Keep,
+                Some("drop") => EmojiPolicy::Drop,
+                Some("bucket") => EmojiPolicy::Bucket,
+                _ => panic!("--emoji needs \"keep\", \"drop\" or \"bucket\""),
+            })
+        }
+        _ => return false,
+    }
+    true
+}
+
+//the value of --sort
+fn parse_sort(value: Option<String>) -> ExportSort {
+    match value.as_deref() {
+        Some("count") => ExportSort::CountDesc,
+        Some("key") => ExportSort::KeyAsc,
+        _ => panic!("--sort needs \"count\" or \"key\""),
+    }
+}
@@ -1,75 +1,420 @@
-use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::path::PathBuf;
+//! # Text_Analysis
+//! Count words in text and the words found near each word (+-5 words), read from *.txt, *.pdf, *.docx and *.odt files or any format added with a `Reader`.
+//!
+//! Results are deterministic: words with the same count are ordered alphabetically, scores are sorted with `f64::total_cmp`
+//! and written with a fixed number of decimals by `format_float`, so the same input gives byte-identical results files on every run and platform.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::{read_dir, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use chrono::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub mod office;
+
+pub use office::{
+    extract_text_from_docx, extract_text_from_docx_with, extract_text_from_odt, extract_text_from_odt_with,
+};
+
+//chars removed from words by trim_to_words
+const REMOVED_CHARS: [char; 21] = [
+    '(', ')', ',', '\"', '.', ';', ':', '=', '[', ']', '{', '}', '-', '_', '/', '\'', '’', '?', '!',
+    '“', '‘',
+];
+
+///Options for `trim_to_words_with`. The default splits words exactly like `trim_to_words`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TokenizerOptions {
+    ///Keep identifiers like `snake_case_name` and version numbers like `1.2.3` as single words: '_' is kept, as is '.' between two digits.
+    pub keep_identifiers: bool,
+    ///Lowercase with Turkish rules: 'İ' becomes 'i' and 'I' becomes dotless 'ı' (otherwise "İ" turns into "i" with a combining dot).
+    pub turkish_casing: bool,
+    ///How text is split into words.
+    pub kind: TokenizerKind,
+    ///Shorten runs of the same letter to at most this many, e.g. with 2 "soooo" becomes "soo" (see `collapse_repeats`).
+    pub collapse_repeats: Option<usize>,
+    ///What to do with emoji. `None` leaves them to the tokenizer: `TokenizerKind::Whitespace` keeps them as part of the
+    ///surrounding word ("great👍" is one word), `TokenizerKind::UnicodeWords` drops them.
+    pub emoji: Option<EmojiPolicy>,
+}
+
+///What `trim_to_words_with` does with emoji. An emoji is a grapheme cluster starting with a pictograph (U+1F000 to U+1FAFF,
+///U+2600 to U+27BF) or containing the emoji presentation selector U+FE0F or a keycap U+20E3, so skin tones (👍🏽) and
+///sequences joined with U+200D (👨‍👩‍👧) are a single emoji.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmojiPolicy {
+    ///Remove emoji; they separate the words around them.
+    Drop,
+    ///Each emoji is a word of its own.
+    Keep,
+    ///Each emoji is counted as the word "<emoji>".
+    Bucket,
+}
+
+///How `trim_to_words_with` splits text into words.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizerKind {
+    ///Split at whitespace and remove punctuation like ',' or '?' (see `trim_to_words`). Fast; "don't" becomes "dont" (default).
+    #[default]
+    Whitespace,
+    ///Unicode word boundaries (UAX #29): words are found by the rules of each script instead of at whitespace. There is no dictionary,
+    ///so Chinese, Japanese kanji and hiragana and Thai come out one character per word; only runs of katakana stay together. Apostrophes, '_' and '.' between letters or digits stay inside words, so "don't", "snake_case" and "1.2.3" are single words.
+    ///Emoji and symbols like "€" or "©" are not words and are dropped (see `TokenizerOptions::emoji` to keep emoji).
+    UnicodeWords,
+}
 
 ///Splits String into single words as Vector<String>.
 ///Splits String at whitespaces and removes chars like , or ?. Change the relevant line to remove or add chars from provided String.
 /// # Example
 /// ```
-/// #[test]
-/// fn test() {
-/// use text_analysiss::trim_to_words;
+/// use text_analysis::trim_to_words;
 /// let words = "(_test] {test2!=".to_string();
-/// let trimmed = trim_to_words(words).unwrap();
+/// let trimmed = trim_to_words(words);
 /// let expected = vec!["test".to_string(), "test2".to_string()];
 /// assert_eq!(trimmed, expected);
-/// }
 /// ```
 pub fn trim_to_words(content: String) -> std::vec::Vec<std::string::String> {
-    let content: Vec<String> = content
-        .to_lowercase()
+    trim_to_words_with(&content, &TokenizerOptions::default())
+}
+
+///Splits String into single words like `trim_to_words`, with `options`.
+/// # Example
+/// ```
+/// use text_analysis::{trim_to_words_with, TokenizerOptions};
+/// let options = TokenizerOptions { keep_identifiers: true, ..TokenizerOptions::default() };
+/// let trimmed = trim_to_words_with("Call parse_config_file (v1.2.3).", &options);
+/// assert_eq!(trimmed, vec!["call", "parse_config_file", "v1.2.3"]);
+/// ```
+pub fn trim_to_words_with(content: &str, options: &TokenizerOptions) -> Vec<String> {
+    split_words(lowercase(content, options), options)
+}
+
+//lowercases, with Turkish rules if set in `options`
+fn lowercase(content: &str, options: &TokenizerOptions) -> String {
+    if options.turkish_casing {
+        content.replace('İ', "i").replace('I', "ı").to_lowercase()
+    } else {
+        content.to_lowercase()
+    }
+}
+
+//splits (already lowercased) text into words as described in `trim_to_words_with`
+fn split_words(content: String, options: &TokenizerOptions) -> Vec<String> {
+    let content = match options.collapse_repeats {
+        Some(max) => collapse_repeats(&content, max),
+        None => content,
+    };
+    let policy = match options.emoji {
+        Some(policy) => policy,
+        None => return split_text(content, options),
+    };
+    //the text between emoji is split by the tokenizer, the emoji are handled by the policy
+    let mut words = Vec::new();
+    let mut text = String::new();
+    for grapheme in content.graphemes(true) {
+        if !is_emoji(grapheme) {
+            text.push_str(grapheme);
+            continue;
+        }
+        match policy {
+            EmojiPolicy::Drop => text.push(' '),
+            EmojiPolicy::Keep | EmojiPolicy::Bucket => {
+                words.extend(split_text(std::mem::take(&mut text), options));
+                words.push(if policy == EmojiPolicy::Keep { grapheme } else { "<emoji>" }.to_string());
+            }
+        }
+    }
+    words.extend(split_text(text, options));
+    words
+}
+
+//emoji as described in `EmojiPolicy`
+fn is_emoji(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(|c| matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF))
+        || grapheme.contains(['\u{FE0F}', '\u{20E3}'])
+}
+
+//splits text without handling emoji, with the tokenizer of `options`
+fn split_text(content: String, options: &TokenizerOptions) -> Vec<String> {
+    if options.kind == TokenizerKind::UnicodeWords {
+        return content.unicode_words().map(String::from).collect();
+    }
+    let content = content
         .replace(&['-'][..], " ")
         //should 's be replaced?
-        .replace("'s", "")
-        .replace(
-            &[
-                '(', ')', ',', '\"', '.', ';', ':', '=', '[', ']', '{', '}', '-', '_', '/', '\'',
-                '’', '?', '!', '“', '‘',
-            ][..],
-            "",
-        )
+        .replace("'s", "");
+    let content = if options.keep_identifiers {
+        let chars: Vec<char> = content.chars().collect();
+        chars
+            .iter()
+            .enumerate()
+            .filter(|&(i, &c)| match c {
+                '_' => true,
+                '.' => {
+                    i > 0
+                        && chars[i - 1].is_ascii_digit()
+                        && chars.get(i + 1).is_some_and(char::is_ascii_digit)
+                }
+                _ => !REMOVED_CHARS.contains(&c),
+            })
+            .map(|(_, &c)| c)
+            .collect()
+    } else {
+        content.replace(&REMOVED_CHARS[..], "")
+    };
+    content
         .split_whitespace()
         .map(String::from)
-        .collect::<Vec<String>>();
-    content
+        .collect::<Vec<String>>()
+}
+
+///Counts how each word of `text` is capitalized, into `forms` (lowercased word -> {word as written -> count}).
+///Words are split like `trim_to_words_with`, so the lowercased words are the ones `word_frequencies` counts.
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use text_analysis::{add_surface_forms, TokenizerOptions};
+/// let mut forms = HashMap::new();
+/// add_surface_forms("Apple sells apples. I like apple pie. APPLE!", &TokenizerOptions::default(), &mut forms);
+/// assert_eq!(forms["apple"].len(), 3);
+/// assert_eq!(forms["apple"]["Apple"], 1);
+/// ```
+pub fn add_surface_forms(
+    text: &str,
+    options: &TokenizerOptions,
+    forms: &mut HashMap<String, HashMap<String, u32>>,
+) {
+    //"'S" is removed like "'s" is after lowercasing
+    for surface in split_words(text.replace("'S", "'s"), options) {
+        let word = lowercase(&surface, options);
+        let word = match options.collapse_repeats {
+            Some(max) => collapse_repeats(&word, max),
+            None => word,
+        };
+        let count = forms.entry(word).or_default().entry(surface).or_insert(0);
+        *count = count.saturating_add(1);
+    }
+}
+
+///Format the surface forms of the words in `counted` (see `add_surface_forms`) for their results file, in the order of `counted`.
+///Each word lists its forms as written, most frequent first or, with `ExportSort::KeyAsc`, alphabetically.
+pub fn format_surface_forms(
+    counted: &[(String, u32)],
+    forms: &HashMap<String, HashMap<String, u32>>,
+    sort: ExportSort,
+) -> String {
+    let mut to_file = String::new();
+    for (word, _) in counted {
+        if let Some(word_forms) = forms.get(word) {
+            let mut word_forms = sort_map_to_vec(word_forms.clone());
+            sort_for_export(&mut word_forms, sort);
+            to_file.push_str(&format!("Word: {:?}, Surface forms: {:?}\n", word, word_forms));
+        }
+    }
+    to_file
+}
+
+///Shorten runs of the same letter longer than `max` to `max` letters, so "soooo goooood" and "sooo good" count alike. A `max` of 0 is treated as 1.
+///Only letters are shortened; numbers like 1000 and punctuation like "..." are kept.
+/// # Example
+/// ```
+/// use text_analysis::collapse_repeats;
+/// assert_eq!(collapse_repeats("soooo goooood!!! 1000", 2), "soo good!!! 1000");
+/// ```
+pub fn collapse_repeats(text: &str, max: usize) -> String {
+    let max = max.max(1);
+    let mut collapsed = String::with_capacity(text.len());
+    let mut previous = None;
+    let mut run = 0;
+    for c in text.chars() {
+        if Some(c) == previous {
+            run += 1;
+        } else {
+            previous = Some(c);
+            run = 1;
+        }
+        if run <= max || !c.is_alphabetic() {
+            collapsed.push(c);
+        }
+    }
+    collapsed
+}
+
+///Drops the words matching `pattern`, e.g. `^[0-9a-f]{8,}$` for hex strings or `[0-9]` for anything with digits.
+///Use on the words returned by `trim_to_words` before counting, so excluded words are neither counted nor listed as words near.
+/// # Example
+/// ```
+/// use regex::Regex;
+/// use text_analysis::{exclude_matching, trim_to_words};
+/// let pattern = Regex::new("^[0-9a-f]{8,}$").unwrap();
+/// let words = exclude_matching(trim_to_words("commit 3f2a9c1b0d fixed it".to_string()), &pattern);
+/// assert_eq!(words, vec!["commit", "fixed", "it"]);
+/// ```
+pub fn exclude_matching(words: Vec<String>, pattern: &regex::Regex) -> Vec<String> {
+    words.into_iter().filter(|word| !pattern.is_match(word)).collect()
+}
+
+///Byte range of a sentence in the original text and its range of words. `token_start..token_end` indexes into the words returned by `trim_to_words_with`
+///for the whole text, with the options passed to `segment_sentences`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SentenceSpan {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub token_start: usize,
+    pub token_end: usize,
+}
+
+//words followed by '.' that don't end a sentence (compared lowercased, without the final '.')
+const ABBREVIATIONS: [&str; 17] = [
+    "dr", "mr", "mrs", "ms", "prof", "st", "jr", "sr", "vs", "etc", "e.g", "i.e", "no", "vol", "pp",
+    "cf", "fig",
+];
+
+///Splits text into sentences ending in '.', '!' or '?' followed by whitespace (closing quotes and brackets stay with the sentence).
+///Abbreviations like "Dr." or "e.g." and single-letter initials don't end a sentence. A blank line (paragraph break) always ends a sentence, so headings without a period stay separate.
+///Trailing text without terminal punctuation is the last sentence.
+/// # Example
+/// ```
+/// use text_analysis::{segment_sentences, TokenizerOptions};
+/// let text = "Is it raining? Dr. Jones thinks so.";
+/// let spans = segment_sentences(text, &TokenizerOptions::default());
+/// assert_eq!(spans.len(), 2);
+/// assert_eq!(&text[spans[1].byte_start..spans[1].byte_end], "Dr. Jones thinks so.");
+/// assert_eq!((spans[1].token_start, spans[1].token_end), (3, 7));
+/// ```
+pub fn segment_sentences(text: &str, options: &TokenizerOptions) -> Vec<SentenceSpan> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut spans = Vec::new();
+    let mut token_start = 0;
+    let mut start: Option<usize> = None;
+    let mut push_span = |byte_start: usize, byte_end: usize, spans: &mut Vec<SentenceSpan>| {
+        let token_end = token_start + trim_to_words_with(&text[byte_start..byte_end], options).len();
+        spans.push(SentenceSpan {
+            byte_start,
+            byte_end,
+            token_start,
+            token_end,
+        });
+        token_start = token_end;
+    };
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        i += 1;
+        let sentence_start = match start {
+            Some(sentence_start) => sentence_start,
+            None if c.is_whitespace() => continue,
+            None => {
+                start = Some(pos);
+                pos
+            }
+        };
+        if c == '\n' {
+            //paragraph break: only whitespace up to another newline
+            let mut j = i;
+            while j < chars.len() && chars[j].1.is_whitespace() && chars[j].1 != '\n' {
+                j += 1;
+            }
+            if j < chars.len() && chars[j].1 == '\n' {
+                let end = sentence_start + text[sentence_start..pos].trim_end().len();
+                push_span(sentence_start, end, &mut spans);
+                start = None;
+                i = j;
+            }
+            continue;
+        }
+        if !matches!(c, '.' | '!' | '?') {
+            continue;
+        }
+        //repeated punctuation, closing quotes and brackets belong to the sentence
+        let mut j = i;
+        while j < chars.len() && matches!(chars[j].1, '.' | '!' | '?' | '"' | '\'' | ')' | ']' | '”' | '’') {
+            j += 1;
+        }
+        if j < chars.len() && !chars[j].1.is_whitespace() {
+            continue;
+        }
+        if c == '.' && is_abbreviation(&text[sentence_start..pos]) {
+            continue;
+        }
+        let end = chars.get(j).map_or(text.len(), |&(end, _)| end);
+        push_span(sentence_start, end, &mut spans);
+        start = None;
+        i = j;
+    }
+    if let Some(sentence_start) = start {
+        push_span(sentence_start, text.trim_end().len(), &mut spans);
+    }
+    spans
 }
+
+//checks if the last word before a '.' is a known abbreviation or a single-letter initial
+fn is_abbreviation(before_dot: &str) -> bool {
+    let word = before_dot
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or("")
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    (word.chars().count() == 1 && word.chars().all(char::is_alphabetic))
+        || ABBREVIATIONS.contains(&word.as_str())
+}
+
 ///Takes &Vec<String> and counts the quantity of each word. Returns Hashmap<String,u32>, with String being the word and u32 the quantity
 /// # Example
 /// ```
-/// #[test]
-/// fn test_count_words() {
-/// use text_analysiss::count_words;
+/// use text_analysis::count_words;
+/// use std::collections::HashMap;
 /// let words = vec![
-///            "one".to_string(),
-///            "two".to_string(),
-///            "two".to_string(),
-///            "three".to_string(),
-///            "three".to_string(),
-///            "three".to_string(),
-///        ];
-///        let counted = count_words(&words);
-///        let mut words_map = HashMap::new();
-///        words_map.insert("one".to_string(), 1 as u32);
-///        words_map.insert("two".to_string(), 2 as u32);
-///        words_map.insert("three".to_string(), 3 as u32);
-///        assert_eq!(counted, words_map);
-/// }
+///     "one".to_string(),
+///     "two".to_string(),
+///     "two".to_string(),
+///     "three".to_string(),
+///     "three".to_string(),
+///     "three".to_string(),
+/// ];
+/// let counted = count_words(&words);
+/// let mut words_map = HashMap::new();
+/// words_map.insert("one".to_string(), 1 as u32);
+/// words_map.insert("two".to_string(), 2 as u32);
+/// words_map.insert("three".to_string(), 3 as u32);
+/// assert_eq!(counted, words_map);
 /// ```
 pub fn count_words(words: &[String]) -> std::collections::HashMap<std::string::String, u32> {
     let mut frequency: HashMap<String, u32> = HashMap::new();
     for word in words {
         //ignore words constiting of only one char?
         //if word.len() > 1 {
-        *frequency.entry(word.to_owned()).or_insert(0) += 1;
+        let count = frequency.entry(word.to_owned()).or_insert(0);
+        *count = count.saturating_add(1);
         //}
     }
     frequency
 }
 
-///Sort words in HashMap<Word, Frequency> according to frequency into Vec<String, u32>.
+///Count the words of `text` split with `options`, without collecting the words near each word.
+///A lean alternative to `count_words_near` for callers that only need frequencies, e.g. for many short texts.
+/// # Example
+/// ```
+/// use text_analysis::{word_frequencies, TokenizerOptions};
+/// let frequency = word_frequencies("The cat saw the dog.", &TokenizerOptions::default());
+/// assert_eq!(frequency["the"], 2);
+/// assert_eq!(frequency.len(), 4);
+/// ```
+pub fn word_frequencies(text: &str, options: &TokenizerOptions) -> HashMap<String, u32> {
+    count_words(&trim_to_words_with(text, options))
+}
+
+///Sort words in HashMap<Word, Frequency> according to frequency into Vec<String, u32>. Words with the same frequency are sorted alphabetically, so the order doesn't change between runs.
 /// # Example
 /// ```
 /// use text_analysis::sort_map_to_vec;
@@ -85,104 +430,2924 @@ pub fn count_words(words: &[String]) -> std::collections::HashMap<std::string::S
 pub fn sort_map_to_vec(
     frequency: HashMap<String, u32>,
 ) -> std::vec::Vec<(std::string::String, u32)> {
-    let mut vec_sorted: Vec<(String, u32)> = frequency.into_iter().collect(); 
-    vec_sorted.sort_by(|a, b| b.1.cmp(&a.1)); 
+    let mut vec_sorted: Vec<(String, u32)> = frequency.into_iter().collect();
+    vec_sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
     vec_sorted
 }
 
+///The `n` least frequent words of `counted` (as returned by `sort_map_to_vec`), rarest first. Words with the same frequency are sorted alphabetically.
+///Rare words are often typos or extraction errors.
+/// # Example
+/// ```
+/// use text_analysis::{bottom_n, count_words, sort_map_to_vec, trim_to_words};
+/// let counted = sort_map_to_vec(count_words(&trim_to_words("the cat teh cat the dgo".to_string())));
+/// assert_eq!(bottom_n(&counted, 2), vec![("dgo".to_string(), 1), ("teh".to_string(), 1)]);
+/// ```
+pub fn bottom_n(counted: &[(String, u32)], n: usize) -> Vec<(String, u32)> {
+    let mut rarest = counted.to_vec();
+    rarest.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    rarest.truncate(n);
+    rarest
+}
+
+///Short summary of `counted` (as returned by `sort_map_to_vec`) for the terminal: number of words and distinct words, then the `top` most frequent words.
+/// # Example
+/// ```
+/// use text_analysis::{format_summary, sort_map_to_vec, word_frequencies, TokenizerOptions};
+/// let counted = sort_map_to_vec(word_frequencies("a b a", &TokenizerOptions::default()));
+/// assert_eq!(format_summary(&counted, 1), "Words: 3, Distinct words: 2\nWord: \"a\", Frequency: 2\n");
+/// ```
+pub fn format_summary(counted: &[(String, u32)], top: usize) -> String {
+    let words = counted
+        .iter()
+        .fold(0_u64, |words, (_, frequency)| words.saturating_add(u64::from(*frequency)));
+    let mut summary = format!("Words: {}, Distinct words: {}\n", words, counted.len());
+    for (word, frequency) in counted.iter().take(top) {
+        summary.push_str(&format!("Word: {:?}, Frequency: {}\n", word, frequency));
+    }
+    summary
+}
+
+///Format the rarest words (see `bottom_n`) for their results file, one word per line.
+pub fn format_rarest(rarest: &[(String, u32)]) -> String {
+    let mut to_file = String::new();
+    for (word, frequency) in rarest {
+        to_file.push_str(&format!("Word: {:?}, Frequency: {}\n", word, frequency));
+    }
+    to_file
+}
+
+///Words before each word that `count_words_near` collects. It collects one word fewer after it (see `get_index_max`).
+pub const DEFAULT_CONTEXT_WINDOW: usize = 5;
+
 ///Get mininum index and guarantee that index is alway >=0
 /// # Example
 /// ```
-///#[test]
-///fn test() {
-///use text_analysiss::get_index_min;
-///let index1 = 5;
-///let min_index1 = get_index_min(&index1).unwrap();
-///assert_eq!(min_index1, 0);
-///}
+/// use text_analysis::get_index_min;
+/// let index1 = 5;
+/// let min_index1 = get_index_min(&index1);
+/// assert_eq!(min_index1, 0);
 /// ```
 pub fn get_index_min(index: &usize) -> usize {
-    if *index as isize - 5 < 0 {
+    if *index < DEFAULT_CONTEXT_WINDOW {
         //check if index -5 would result in negative number, return 0 in case
         0
     } else {
         //if index-5 > 0, return index-5
-        index - 5
+        index - DEFAULT_CONTEXT_WINDOW
     }
 }
 
 ///Get maximum index and garantee that index does not exeed total length of Vec
 /// # Example
 /// ```
-/// #[test]
-/// fn test() {
 /// use text_analysis::get_index_max;
 /// let index1 = 5;
-/// let max_index1 = get_index_max(&index1, &9).unwrap();
+/// let max_index1 = get_index_max(&index1, &9);
 /// assert_eq!(max_index1, 9);
-/// }
 /// ```
 pub fn get_index_max(index: &usize, max_len: &usize) -> usize {
-    if index + 5 > *max_len {
-        *max_len as usize
+    //exclusive, so the word at index + 5 is not included
+    if index + DEFAULT_CONTEXT_WINDOW > *max_len {
+        *max_len
     } else {
-        index + 5
+        index + DEFAULT_CONTEXT_WINDOW
     }
 }
 
-///save file to path. Return result.
-pub fn save_file(to_file: String, mut path: PathBuf) -> std::io::Result<PathBuf> {
-    let local: DateTime<Local> = Local::now();
-    let new_filename: String = local
-        .format("%Y_%m_%d_%H_%M_%S_results_word_analysis.txt")
-        .to_string();
-    path.push(new_filename);
+///Counts every word in `content_vec` into `frequency` and pushes the words near it into `words_near_vec_map`:
+///the 5 words before it and the 4 words after it. `count_words_near_within(…, 5)` pushes 5 words on each side.
+///Call once per document so that words near each other in different documents are not mixed up.
+///Counts stop at `u32::MAX` instead of overflowing, so very large corpora can't wrap around to small counts.
+/// # Example
+/// ```
+/// use text_analysis::{count_words_near, trim_to_words};
+/// use std::collections::HashMap;
+/// let mut frequency = HashMap::new();
+/// let mut words_near_vec_map = HashMap::new();
+/// let content_vec = trim_to_words("one two two".to_string());
+/// count_words_near(&content_vec, &mut frequency, &mut words_near_vec_map);
+/// assert_eq!(frequency["two"], 2);
+/// assert_eq!(words_near_vec_map["one"], vec!["two".to_string(), "two".to_string()]);
+/// ```
+pub fn count_words_near(
+    content_vec: &[String],
+    frequency: &mut HashMap<String, u32>,
+    words_near_vec_map: &mut HashMap<String, Vec<String>>,
+) {
+    for (index, word) in content_vec.iter().enumerate() {
+        let count = frequency.entry(word.to_owned()).or_insert(0);
+        *count = count.saturating_add(1);
 
-    let mut file = OpenOptions::new().write(true).create(true).open(&path)?;
+        let min: usize = get_index_min(&index);
+        let max: usize = get_index_max(&index, &content_vec.len());
 
-    file.write_all(to_file.as_bytes())?;
+        let words_near = words_near_vec_map.entry(word.to_owned()).or_default();
+        for (number, value) in content_vec.iter().enumerate().take(max).skip(min) {
+            if number != index {
+                words_near.push(value.clone()); //pushes -+5 words to vec
+            }
+        }
+    }
+}
 
-    Ok(path)
+///Like `count_words_near`, but pushes the words within +-`window` of each word into `words_near_vec_map`.
+/// # Example
+/// ```
+/// use text_analysis::{count_words_near_within, trim_to_words};
+/// use std::collections::HashMap;
+/// let mut frequency = HashMap::new();
+/// let mut words_near_vec_map = HashMap::new();
+/// let content_vec = trim_to_words("one two three four".to_string());
+/// count_words_near_within(&content_vec, &mut frequency, &mut words_near_vec_map, 1);
+/// assert_eq!(words_near_vec_map["two"], vec!["one".to_string(), "three".to_string()]);
+/// ```
+pub fn count_words_near_within(
+    content_vec: &[String],
+    frequency: &mut HashMap<String, u32>,
+    words_near_vec_map: &mut HashMap<String, Vec<String>>,
+    window: usize,
+) {
+    for (index, word) in content_vec.iter().enumerate() {
+        let count = frequency.entry(word.to_owned()).or_insert(0);
+        *count = count.saturating_add(1);
+
+        let min = index.saturating_sub(window);
+        let max = index.saturating_add(window).saturating_add(1).min(content_vec.len());
+
+        let words_near = words_near_vec_map.entry(word.to_owned()).or_default();
+        for (number, value) in content_vec.iter().enumerate().take(max).skip(min) {
+            if number != index {
+                words_near.push(value.clone());
+            }
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn test_count() {
-        let words = vec![
-            "one".to_string(),
-            "two".to_string(),
-            "two".to_string(),
-            "three".to_string(),
-            "three".to_string(),
-            "three".to_string(),
-        ];
-        let counted = count_words(&words);
-        let mut words_map = HashMap::new();
-        words_map.insert("one".to_string(), 1 as u32);
-        words_map.insert("two".to_string(), 2 as u32);
-        words_map.insert("three".to_string(), 3 as u32);
-        assert_eq!(counted, words_map);
+//words shorter than this are never merged into a base form
+const MIN_PLURAL_LENGTH: usize = 4;
+
+//words ending in "s" that are not plurals of a word that may also occur ("news" is not "new", "yours" is not "your")
+const NOT_PLURALS: [&str; 13] = [
+    "news", "does", "means", "lens", "series", "species", "always", "perhaps", "yours", "ours", "hers",
+    "goods", "sometimes",
+];
+
+///Plural and simple morphological variants found in `frequency`, mapped to the form they are merged into (English only, lighter than stemming).
+///A word is merged only if the merged form also occurs in `frequency` at least `min_count` times:
+///"-ies" -> "-y" (studies -> study), "-ses" -> "-sis" (analyses -> analysis), "-es" after s, x, z, ch or sh (boxes -> box)
+///and a final "s" not preceded by 's' (cats -> cat). Words of fewer than four letters ("is", "its") and a few words like "news", "species"
+///or "yours" that only look like plurals are never merged.
+/// # Example
+/// ```
+/// use text_analysis::light_normalize_map;
+/// use std::collections::HashMap;
+/// let frequency: HashMap<String, u32> = [("cats", 2), ("cat", 1), ("dogs", 1)]
+///     .iter()
+///     .map(|(word, count)| (word.to_string(), *count))
+///     .collect();
+/// let remap = light_normalize_map(&frequency, 1);
+/// assert_eq!(remap["cats"], "cat");
+/// assert!(!remap.contains_key("dogs"));
+/// ```
+pub fn light_normalize_map(frequency: &HashMap<String, u32>, min_count: u32) -> HashMap<String, String> {
+    let occurs = |word: &str| frequency.get(word).is_some_and(|count| *count >= min_count);
+    let mut remap = HashMap::new();
+    for word in frequency.keys() {
+        //short words like "is", "as" or "its" are not plurals of "i", "a" or "it"
+        if word.chars().count() < MIN_PLURAL_LENGTH || NOT_PLURALS.contains(&word.as_str()) {
+            continue;
+        }
+        let mut candidates = Vec::new();
+        if let Some(stem) = word.strip_suffix("ies") {
+            candidates.push(format!("{}y", stem));
+        }
+        if let Some(stem) = word.strip_suffix("ses") {
+            candidates.push(format!("{}sis", stem));
+        }
+        if let Some(stem) = word.strip_suffix("es") {
+            if ["s", "x", "z", "ch", "sh"].iter().any(|sibilant| stem.ends_with(sibilant)) {
+                candidates.push(stem.to_string());
+            }
+        }
+        if let Some(stem) = word.strip_suffix('s') {
+            if !stem.ends_with('s') && stem.chars().count() >= MIN_PLURAL_LENGTH - 1 {
+                candidates.push(stem.to_string());
+            }
+        }
+        if let Some(base) = candidates.into_iter().find(|base| !base.is_empty() && occurs(base)) {
+            remap.insert(word.clone(), base);
+        }
     }
+    remap
+}
 
-    #[test]
-    fn test_max_min_index() {
-        let index1 = 5;
-        let min_index1 = get_index_min(&index1);
-        let max_index1 = get_index_max(&index1, &9);
-        assert_eq!(min_index1, 0);
-        assert_eq!(max_index1, 9);
-        let index2 = 0;
-        let min_index2 = get_index_min(&index2);
-        let max_index2 = get_index_max(&index2, &5);
-        assert_eq!(min_index2, 0);
-        assert_eq!(max_index2, 5);
-        let index3 = 100;
-        let min_index3 = get_index_min(&index3);
-        let max_index3 = get_index_max(&index3, &103);
-        assert_eq!(min_index3, 95);
-        assert_eq!(max_index3, 103);
+///Merge the variants in `remap` (see `light_normalize_map`) into their base form: their counts are added to the base form
+///and they are replaced by the base form in the words near each word.
+pub fn apply_light_normalization(
+    remap: &HashMap<String, String>,
+    frequency: &mut HashMap<String, u32>,
+    words_near_vec_map: &mut HashMap<String, Vec<String>>,
+) {
+    //follows chains like "analyseses" -> "analyses" -> "analysis", stopping at cycles
+    let resolve = |word: &str| -> String {
+        let mut base = word;
+        let mut steps = 0;
+        while let Some(next) = remap.get(base) {
+            base = next;
+            steps += 1;
+            if steps > remap.len() {
+                break;
+            }
+        }
+        base.to_string()
+    };
+    for variant in remap.keys() {
+        let base = resolve(variant);
+        if let Some(count) = frequency.remove(variant) {
+            let base_count = frequency.entry(base.clone()).or_insert(0);
+            *base_count = base_count.saturating_add(count);
+        }
+        if let Some(words) = words_near_vec_map.remove(variant) {
+            words_near_vec_map.entry(base).or_default().extend(words);
+        }
+    }
+    for words in words_near_vec_map.values_mut() {
+        for word in words.iter_mut() {
+            if remap.contains_key(word) {
+                *word = resolve(word);
+            }
+        }
+    }
+}
+
+///Counts the length in words (split with `options`) of each sentence of `text` (see `segment_sentences`) into `histogram` (length -> number of sentences).
+///Sentences without words are not counted. Call once per document.
+/// # Example
+/// ```
+/// use text_analysis::{add_sentence_lengths, TokenizerOptions};
+/// use std::collections::HashMap;
+/// let mut histogram = HashMap::new();
+/// add_sentence_lengths("One two. Three four! Five.", &TokenizerOptions::default(), &mut histogram);
+/// assert_eq!(histogram[&2], 2);
+/// assert_eq!(histogram[&1], 1);
+/// ```
+pub fn add_sentence_lengths(text: &str, options: &TokenizerOptions, histogram: &mut HashMap<usize, usize>) {
+    for span in segment_sentences(text, options) {
+        let length = span.token_end - span.token_start;
+        if length > 0 {
+            *histogram.entry(length).or_insert(0) += 1;
+        }
+    }
+}
+
+///Number of sentences, mean and (population) standard deviation of the sentence lengths in `histogram`. Mean and standard deviation are 0 without sentences.
+pub fn sentence_length_stats(histogram: &HashMap<usize, usize>) -> (usize, f64, f64) {
+    let sentences: usize = histogram.values().sum();
+    if sentences == 0 {
+        return (0, 0.0, 0.0);
+    }
+    let mean = histogram
+        .iter()
+        .map(|(length, count)| *length as f64 * *count as f64)
+        .sum::<f64>()
+        / sentences as f64;
+    let variance = histogram
+        .iter()
+        .map(|(length, count)| (*length as f64 - mean).powi(2) * *count as f64)
+        .sum::<f64>()
+        / sentences as f64;
+    (sentences, mean, variance.sqrt())
+}
+
+///Format the sentence length distribution for its results file: number of sentences, mean and standard deviation (with `precision` or three decimals),
+///then one `length, sentences` line per length, shortest first.
+pub fn format_sentence_lengths(
+    histogram: &HashMap<usize, usize>,
+    precision: Option<usize>,
+) -> Result<String, String> {
+    let precision = precision.unwrap_or(DEFAULT_PRECISION);
+    let (sentences, mean, std_dev) = sentence_length_stats(histogram);
+    let mut to_file = format!(
+        "Sentences: {}, Mean length: {}, Standard deviation: {}\n\nlength, sentences\n",
+        sentences,
+        format_float(mean, precision)?,
+        format_float(std_dev, precision)?
+    );
+    let mut lengths: Vec<(&usize, &usize)> = histogram.iter().collect();
+    lengths.sort();
+    for (length, count) in lengths {
+        to_file.push_str(&format!("{}, {}\n", length, count));
+    }
+    Ok(to_file)
+}
+
+///Vocabulary growth: the number of distinct words (types) seen after every `interval` words (tokens).
+///Feed the words of each file in reading order with `add`; the curve runs over all files.
+/// # Example
+/// ```
+/// use text_analysis::{trim_to_words, VocabularyGrowth};
+/// let mut growth = VocabularyGrowth::new(2);
+/// growth.add(&trim_to_words("a b a c a".to_string()));
+/// assert_eq!(growth.points(), vec![(2, 2), (4, 3), (5, 3)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VocabularyGrowth {
+    interval: usize,
+    types: HashSet<String>,
+    tokens_seen: usize,
+    points: Vec<(usize, usize)>,
+}
+
+impl VocabularyGrowth {
+    ///Sample the number of types after every `interval` tokens. An interval of 0 is treated as 1.
+    pub fn new(interval: usize) -> VocabularyGrowth {
+        VocabularyGrowth {
+            interval: interval.max(1),
+            types: HashSet::new(),
+            tokens_seen: 0,
+            points: Vec::new(),
+        }
+    }
+
+    ///Add the next words of the token stream.
+    pub fn add(&mut self, tokens: &[String]) {
+        for token in tokens {
+            if !self.types.contains(token) {
+                self.types.insert(token.clone());
+            }
+            self.tokens_seen += 1;
+            if self.tokens_seen.is_multiple_of(self.interval) {
+                self.points.push((self.tokens_seen, self.types.len()));
+            }
+        }
+    }
+
+    ///(tokens seen, types seen) after every interval, ending with the totals if they don't fall on an interval.
+    pub fn points(&self) -> Vec<(usize, usize)> {
+        let mut points = self.points.clone();
+        if !self.tokens_seen.is_multiple_of(self.interval) {
+            points.push((self.tokens_seen, self.types.len()));
+        }
+        points
+    }
+}
+
+///Format the vocabulary growth curve for its results file, one `tokens_seen, types_seen` line per point after a header.
+pub fn format_vocabulary_growth(points: &[(usize, usize)]) -> String {
+    let mut to_file = String::from("tokens_seen, types_seen\n");
+    for (tokens_seen, types_seen) in points {
+        to_file.push_str(&format!("{}, {}\n", tokens_seen, types_seen));
+    }
+    to_file
+}
+
+///Up to `k` example sentences for each of the `top` most frequent words, chosen by reservoir sampling so every sentence containing the word is equally likely to be kept.
+///The random numbers come from a fixed seed, so the same input always gives the same examples.
+///To bound memory, sentences are only kept for words at or above a running frequency floor: when more than four times `top` words have samples,
+///only the `2 * top` most frequent of them keep their samples and the floor is raised to the count of the last one kept.
+///A word that only becomes frequent late in the corpus gets its examples from the text after that point.
+/// # Example
+/// ```
+/// use text_analysis::{ExampleSentences, TokenizerOptions};
+/// let mut examples = ExampleSentences::new(1, 50, 42);
+/// examples.add("The cat sat. A dog barked.", "a.txt", &TokenizerOptions::default());
+/// assert_eq!(examples.get("dog"), vec![("a.txt", "A dog barked.")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleSentences {
+    k: usize,
+    top: usize,
+    rng: u64,
+    //number of sentences containing each word so far
+    seen: HashMap<String, u64>,
+    //words seen in fewer sentences are not sampled
+    floor: u64,
+    //sentences containing the word since it was sampled, and the (index into `sources`, sentence) samples
+    samples: HashMap<String, (u64, Vec<(usize, String)>)>,
+    sources: Vec<String>,
+}
+
+impl ExampleSentences {
+    ///Keep up to `k` sentences for each of the `top` most frequent words, sampled with random numbers from `seed`.
+    pub fn new(k: usize, top: usize, seed: u64) -> ExampleSentences {
+        ExampleSentences {
+            k,
+            top: top.max(1),
+            rng: seed,
+            seen: HashMap::new(),
+            floor: 0,
+            samples: HashMap::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    ///Add the sentences of `text` (see `segment_sentences`), read from `source`. Sentences are stored with their whitespace collapsed to single spaces.
+    pub fn add(&mut self, text: &str, source: &str, options: &TokenizerOptions) {
+        if self.k == 0 {
+            return;
+        }
+        if self.sources.last().map(String::as_str) != Some(source) {
+            self.sources.push(source.to_string());
+        }
+        let source = self.sources.len() - 1;
+        for span in segment_sentences(text, options) {
+            let sentence = &text[span.byte_start..span.byte_end];
+            let words: HashSet<String> = trim_to_words_with(sentence, options).into_iter().collect();
+            let mut sentence_words: Vec<String> = words.into_iter().collect();
+            //same order on every run, so the seeded random numbers pick the same sentences
+            sentence_words.sort();
+            for word in sentence_words {
+                let seen = self.seen.entry(word.clone()).or_insert(0);
+                *seen += 1;
+                if *seen < self.floor {
+                    continue;
+                }
+                let (sampled, samples) = self.samples.entry(word).or_default();
+                *sampled += 1;
+                let slot = if *sampled <= self.k as u64 {
+                    None
+                } else {
+                    match next_random(&mut self.rng) % *sampled {
+                        j if j < self.k as u64 => Some(j as usize),
+                        _ => continue,
+                    }
+                };
+                let example = (source, sentence.split_whitespace().collect::<Vec<_>>().join(" "));
+                match slot {
+                    Some(j) => samples[j] = example,
+                    None => samples.push(example),
+                }
+            }
+            if self.samples.len() > 4 * self.top {
+                self.raise_floor();
+            }
+        }
+    }
+
+    //keep the samples of the 2 * top most frequent words, ties broken alphabetically so runs repeat
+    fn raise_floor(&mut self) {
+        let mut words: Vec<(u64, String)> =
+            self.samples.keys().map(|word| (self.seen[word], word.clone())).collect();
+        words.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        words.truncate(2 * self.top);
+        self.floor = words.last().map_or(0, |(count, _)| *count);
+        let keep: HashSet<String> = words.into_iter().map(|(_, word)| word).collect();
+        self.samples.retain(|word, _| keep.contains(word));
+    }
+
+    ///The (source, sentence) examples kept for `word`.
+    pub fn get(&self, word: &str) -> Vec<(&str, &str)> {
+        self.samples
+            .get(word)
+            .map(|(_, samples)| {
+                samples
+                    .iter()
+                    .map(|(source, sentence)| (self.sources[*source].as_str(), sentence.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+//SplitMix64, small and good enough to pick sentences
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+///Format the example sentences of the `top` most frequent words of `counted` for their results file, one example per line.
+pub fn format_examples(examples: &ExampleSentences, counted: &[(String, u32)], top: usize) -> String {
+    let mut to_file = String::new();
+    for (word, _) in counted.iter().take(top) {
+        for (source, sentence) in examples.get(word) {
+            to_file.push_str(&format!(
+                "Word: {:?}, Source: {:?}, Example: {:?}\n",
+                word, source, sentence
+            ));
+        }
+    }
+    to_file
+}
+
+///Pause before trying again to read a file that was not found.
+pub const READ_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+///Read file to bytes. If the file is not found (e.g. deleted or replaced after the directory was scanned), retry up to `retries` times after a short pause.
+///The error of the last attempt is returned, so callers can check for `ErrorKind::NotFound` to tell vanished files from unreadable ones.
+pub fn read_with_retries(path: &Path, retries: u32) -> std::io::Result<Vec<u8>> {
+    let mut attempt = 0;
+    loop {
+        match std::fs::read(path) {
+            Err(e) if e.kind() == ErrorKind::NotFound && attempt < retries => {
+                attempt += 1;
+                thread::sleep(READ_RETRY_BACKOFF);
+            }
+            result => return result,
+        }
+    }
+}
+
+///Text extracted from a PDF together with the number of pages and how many of them could not be read.
+#[derive(Debug)]
+pub struct PdfText {
+    pub text: String,
+    pub pages: usize,
+    pub page_errors: usize,
+}
+
+///Extract text from PDF bytes page by page. Pages that fail to parse (or make pdf-extract panic) are skipped and counted in `page_errors`.
+///Returns an error if the document can't be loaded or if every page fails. With `strict` set, the first broken page fails the whole document.
+pub fn extract_pdf_text(bytes: &[u8], strict: bool) -> Result<PdfText, Box<dyn std::error::Error>> {
+    let mut doc = pdf_extract::Document::load_mem(bytes)?;
+    if doc.is_encrypted() {
+        doc.decrypt("")?;
+    }
+    let pages = doc.get_pages();
+    let mut text = String::new();
+    let mut page_errors = 0;
+    for page_num in pages.keys() {
+        //pdf-extract panics on some malformed pages instead of returning an error
+        let page = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut page_text = String::new();
+            {
+                let mut output = pdf_extract::PlainTextOutput::new(&mut page_text);
+                pdf_extract::output_doc_page(&doc, &mut output, *page_num)?;
+            }
+            Ok::<String, pdf_extract::OutputError>(page_text)
+        }));
+        match page {
+            Ok(Ok(page_text)) => {
+                //a page end is a paragraph break for segment_sentences
+                if !text.is_empty() {
+                    text.push_str("\n\n");
+                }
+                text.push_str(&page_text)
+            }
+            Ok(Err(e)) if strict => return Err(format!("page {}: {}", page_num, e).into()),
+            Err(_) if strict => return Err(format!("page {}: pdf-extract panicked", page_num).into()),
+            _ => page_errors += 1,
+        }
+    }
+    if !pages.is_empty() && page_errors == pages.len() {
+        return Err(format!("none of {} pages could be read", pages.len()).into());
+    }
+    Ok(PdfText {
+        text,
+        pages: pages.len(),
+        page_errors,
+    })
+}
+
+///Error returned by a `Reader`.
+#[derive(Debug)]
+pub enum ReadError {
+    ///The file could not be read from disk.
+    Io(std::io::Error),
+    ///The file was read but its content could not be turned into text.
+    Format(String),
+    ///The file is not valid UTF-8 and was skipped (see `InvalidUtf8Policy::Skip`).
+    InvalidUtf8(String),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Format(message) => write!(f, "{}", message),
+            ReadError::InvalidUtf8(message) => write!(f, "not valid UTF-8: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl From<std::io::Error> for ReadError {
+    fn from(e: std::io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+///Why a file was not analyzed (see `FileWarning`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    ///The file could not be read or its content could not be turned into text.
+    Unreadable,
+    ///The file was removed after the directory was scanned.
+    Vanished,
+    ///The file is not valid UTF-8 (`InvalidUtf8Policy::Skip`).
+    InvalidUtf8,
+    ///The text looks like a failed extraction (see `suspect_text`). The file is still analyzed unless the caller drops it.
+    SuspectText,
+}
+
+///A file that was skipped, with the reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileWarning {
+    pub path: PathBuf,
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl FileWarning {
+    ///The warning for a file whose reader returned `error`.
+    /// # Example
+    /// ```
+    /// use text_analysis::{FileWarning, ReadError, WarningKind};
+    /// use std::io::{Error, ErrorKind};
+    /// let error = ReadError::Io(Error::new(ErrorKind::NotFound, "gone"));
+    /// assert_eq!(FileWarning::from_read_error("a.txt".into(), &error).kind, WarningKind::Vanished);
+    /// ```
+    pub fn from_read_error(path: PathBuf, error: &ReadError) -> Self {
+        let kind = match error {
+            ReadError::Io(e) if e.kind() == ErrorKind::NotFound => WarningKind::Vanished,
+            ReadError::InvalidUtf8(_) => WarningKind::InvalidUtf8,
+            _ => WarningKind::Unreadable,
+        };
+        FileWarning {
+            path,
+            kind,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FileWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            WarningKind::Unreadable => write!(f, "Could not read {:?}: {}", self.path, self.message),
+            WarningKind::Vanished => write!(
+                f,
+                "Skipped {:?}: file was removed before it could be read",
+                self.path
+            ),
+            WarningKind::InvalidUtf8 => write!(f, "Skipped {:?}: not valid UTF-8", self.path),
+            WarningKind::SuspectText => {
+                write!(f, "Suspect text extraction in {:?}: {}", self.path, self.message)
+            }
+        }
+    }
+}
+
+///Log of a run with one JSON line per file, appended and flushed as each file is done so it survives a crash.
+///Lines look like `{"time":"2024-05-01T12:03:59Z","file":"a.txt","words":120}`, with `"warning"` and `"message"` added for skipped or suspect files
+///(`"words"` is missing if the file was not analyzed). Can be shared between threads.
+#[derive(Debug)]
+pub struct RunLog {
+    file: std::sync::Mutex<std::fs::File>,
+}
+
+impl RunLog {
+    ///Open the log at `path`, appending if it exists.
+    pub fn open(path: &Path) -> std::io::Result<RunLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(RunLog {
+            file: std::sync::Mutex::new(file),
+        })
+    }
+
+    ///Log that `file` was processed: the number of `words` analyzed (None if it was skipped) and its `warning`, if any.
+    pub fn log_file(
+        &self,
+        file: &str,
+        words: Option<usize>,
+        warning: Option<&FileWarning>,
+    ) -> std::io::Result<()> {
+        let mut line = format!(
+            "{{\"time\":{},\"file\":{}",
+            json_string(&Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+            json_string(file)
+        );
+        if let Some(words) = words {
+            line.push_str(&format!(",\"words\":{}", words));
+        }
+        if let Some(warning) = warning {
+            line.push_str(&format!(
+                ",\"warning\":{},\"message\":{}",
+                json_string(warning_kind_name(warning.kind)),
+                json_string(&warning.message)
+            ));
+        }
+        line.push_str("}\n");
+        //a poisoned lock only means another thread panicked while logging; the file is still usable
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    }
+}
+
+//name of `kind` in the JSON of the run log and the run report
+fn warning_kind_name(kind: WarningKind) -> &'static str {
+    match kind {
+        WarningKind::Unreadable => "unreadable",
+        WarningKind::Vanished => "vanished",
+        WarningKind::InvalidUtf8 => "invalid_utf8",
+        WarningKind::SuspectText => "suspect_text",
+    }
+}
+
+///Report of a run as one line of JSON: the analyzed `path`, the number of `files` analyzed, the number of words and distinct words in `counted`
+///(as returned by `sort_map_to_vec`) and its `top` most frequent words, the `warnings` about skipped files, the `hints` and the `timings` in milliseconds.
+/// # Example
+/// ```
+/// use text_analysis::{format_run_report, Timings};
+/// let counted = vec![("cat".to_string(), 2), ("dog".to_string(), 1)];
+/// let report = format_run_report("texts", 1, &counted, 1, &[], &[], &Timings::default());
+/// assert!(report.starts_with(
+///     r#"{"path":"texts","files":1,"words":3,"distinct_words":2,"top_words":[{"word":"cat","count":2}],"warnings":[],"hints":[],"#
+/// ));
+/// ```
+pub fn format_run_report(
+    path: &str,
+    files: usize,
+    counted: &[(String, u32)],
+    top: usize,
+    warnings: &[FileWarning],
+    hints: &[String],
+    timings: &Timings,
+) -> String {
+    let words = counted
+        .iter()
+        .fold(0_u64, |words, (_, frequency)| words.saturating_add(u64::from(*frequency)));
+    let top_words: Vec<String> = counted
+        .iter()
+        .take(top)
+        .map(|(word, frequency)| format!("{{\"word\":{},\"count\":{}}}", json_string(word), frequency))
+        .collect();
+    let warnings: Vec<String> = warnings
+        .iter()
+        .map(|warning| {
+            format!(
+                "{{\"file\":{},\"warning\":{},\"message\":{}}}",
+                json_string(&warning.path.display().to_string()),
+                json_string(warning_kind_name(warning.kind)),
+                json_string(&warning.message)
+            )
+        })
+        .collect();
+    let hints: Vec<String> = hints.iter().map(|hint| json_string(hint)).collect();
+    let milliseconds = |stage: Duration| format!("{:.3}", stage.as_secs_f64() * 1000.0);
+    format!(
+        "{{\"path\":{},\"files\":{},\"words\":{},\"distinct_words\":{},\"top_words\":[{}],\"warnings\":[{}],\"hints\":[{}],\"timings_ms\":{{\"read\":{},\"tokenize\":{},\"count\":{},\"sort\":{},\"write\":{}}}}}\n",
+        json_string(path),
+        files,
+        words,
+        counted.len(),
+        top_words.join(","),
+        warnings.join(","),
+        hints.join(","),
+        milliseconds(timings.read),
+        milliseconds(timings.tokenize),
+        milliseconds(timings.count),
+        milliseconds(timings.sort),
+        milliseconds(timings.write)
+    )
+}
+
+//`s` as a quoted JSON string
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+///When `suspect_text` flags the words of a file as a likely failed extraction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuspectThresholds {
+    ///Files with fewer words are never flagged; the ratios of short texts say little.
+    pub min_words: usize,
+    ///Flag if more than this share of the words is a single character, e.g. "t h e" from a PDF with odd character spacing.
+    pub max_single_char_ratio: f64,
+    ///Flag if more than this share of the words contains U+FFFD, the replacement for undecodable bytes.
+    pub max_replacement_ratio: f64,
+    ///Flag if the mean word length in characters is below this.
+    pub min_mean_length: f64,
+}
+
+impl Default for SuspectThresholds {
+    fn default() -> Self {
+        SuspectThresholds {
+            min_words: 50,
+            max_single_char_ratio: 0.3,
+            max_replacement_ratio: 0.05,
+            min_mean_length: 2.5,
+        }
+    }
+}
+
+///Check the words of one file for signs of garbage from a failed extraction (wrong encoding, CID fonts without a mapping).
+///Returns why the text is suspect, or None if it looks like normal text.
+///Single-character words and word length are only checked on words in Latin, Greek or Cyrillic script: in Chinese or Japanese
+///a single character is a normal word (see `TokenizerKind::UnicodeWords`).
+/// # Example
+/// ```
+/// use text_analysis::{suspect_text, trim_to_words, SuspectThresholds};
+/// let garbage = trim_to_words("T h e c a t s a t o n t h e m a t . ".repeat(5));
+/// assert!(suspect_text(&garbage, &SuspectThresholds::default()).is_some());
+/// let normal = trim_to_words("The cat sat on the mat and looked out of the window. ".repeat(5));
+/// assert_eq!(suspect_text(&normal, &SuspectThresholds::default()), None);
+/// ```
+pub fn suspect_text(words: &[String], thresholds: &SuspectThresholds) -> Option<String> {
+    if words.is_empty() || words.len() < thresholds.min_words {
+        return None;
+    }
+    let mut reasons = Vec::new();
+    let spaced: Vec<&String> = words.iter().filter(|word| is_spaced_script(word)).collect();
+    if !spaced.is_empty() && spaced.len() >= thresholds.min_words {
+        let total = spaced.len() as f64;
+        let single_char = spaced.iter().filter(|word| word.chars().count() == 1).count() as f64 / total;
+        let mean_length = spaced.iter().map(|word| word.chars().count()).sum::<usize>() as f64 / total;
+        if single_char > thresholds.max_single_char_ratio {
+            reasons.push(format!("{:.0}% single-character words", single_char * 100.0));
+        }
+        if mean_length < thresholds.min_mean_length {
+            reasons.push(format!("mean word length {:.1}", mean_length));
+        }
+    }
+    let replacement = words.iter().filter(|word| word.contains('\u{FFFD}')).count() as f64 / words.len() as f64;
+    if replacement > thresholds.max_replacement_ratio {
+        reasons.push(format!("{:.0}% words with replacement characters", replacement * 100.0));
+    }
+    if reasons.is_empty() {
+        None
+    } else {
+        Some(reasons.join(", "))
+    }
+}
+
+//true if all letters of `word` are Latin, Greek or Cyrillic, scripts with spaces between words of usually several letters
+fn is_spaced_script(word: &str) -> bool {
+    word.chars().filter(|c| c.is_alphabetic()).all(|c| {
+        matches!(c as u32,
+            0..=0x024F // Latin, Latin-1 and Latin Extended
+            | 0x0370..=0x052F // Greek and Cyrillic
+            | 0x1E00..=0x1FFF // Latin Extended Additional and Greek Extended
+        )
+    })
+}
+
+///Turns a file into text. Implement this to analyze file formats the crate doesn't support and add it to the readers passed to `collect_files` and `find_reader`.
+pub trait Reader: Send + Sync {
+    ///File extensions (without the dot, lowercase) this reader handles.
+    fn extensions(&self) -> &[&str];
+    ///Read the file at `path` and return its text.
+    fn read(&self, path: &Path) -> Result<String, ReadError>;
+}
+
+///What `TxtReader` does with files that are not valid UTF-8.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidUtf8Policy {
+    ///Fail to read the file with `ReadError::Format` (default).
+    #[default]
+    Fail,
+    ///Replace each invalid sequence with U+FFFD, report the number of replacements on stderr and analyze the file.
+    Replace,
+    ///Fail to read the file with `ReadError::InvalidUtf8`, so it can be reported apart from other errors.
+    Skip,
+}
+
+///Decode `bytes` as UTF-8, replacing each invalid sequence with U+FFFD. Returns the text and the number of replacements.
+/// # Example
+/// ```
+/// use text_analysis::decode_utf8_lossy;
+/// assert_eq!(decode_utf8_lossy(b"caf\xe9 au lait"), ("caf\u{FFFD} au lait".to_string(), 1));
+/// ```
+pub fn decode_utf8_lossy(bytes: &[u8]) -> (String, usize) {
+    let replacements = bytes
+        .utf8_chunks()
+        .filter(|chunk| !chunk.invalid().is_empty())
+        .count();
+    (String::from_utf8_lossy(bytes).into_owned(), replacements)
+}
+
+///Reads UTF-8 encoded *.txt files. A byte order mark at the start of the file is removed, so it doesn't stick to the first word.
+pub struct TxtReader {
+    ///How often to retry a file that was not found, see `read_with_retries`.
+    pub retries: u32,
+    ///What to do with files that are not valid UTF-8.
+    pub invalid_utf8: InvalidUtf8Policy,
+}
+
+impl Reader for TxtReader {
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    fn read(&self, path: &Path) -> Result<String, ReadError> {
+        let mut bytes = read_with_retries(path, self.retries)?;
+        if bytes.starts_with(b"\xEF\xBB\xBF") {
+            bytes.drain(..3);
+        }
+        match self.invalid_utf8 {
+            InvalidUtf8Policy::Fail => String::from_utf8(bytes).map_err(|e| ReadError::Format(e.to_string())),
+            InvalidUtf8Policy::Skip => String::from_utf8(bytes).map_err(|e| ReadError::InvalidUtf8(e.to_string())),
+            InvalidUtf8Policy::Replace => {
+                let (text, replacements) = decode_utf8_lossy(&bytes);
+                if replacements > 0 {
+                    eprintln!(
+                        "Warning: {} invalid UTF-8 sequence(s) in {:?} were replaced with U+FFFD",
+                        replacements, path
+                    );
+                }
+                Ok(text)
+            }
+        }
+    }
+}
+
+///Reads *.pdf files page by page with `extract_pdf_text`. Pages that can't be read are reported on stderr.
+pub struct PdfReader {
+    ///Fail the whole document if a single page can't be read.
+    pub strict: bool,
+    ///How often to retry a file that was not found, see `read_with_retries`.
+    pub retries: u32,
+}
+
+impl Reader for PdfReader {
+    fn extensions(&self) -> &[&str] {
+        &["pdf"]
+    }
+
+    fn read(&self, path: &Path) -> Result<String, ReadError> {
+        let bytes = read_with_retries(path, self.retries)?;
+        let pdf = extract_pdf_text(&bytes, self.strict).map_err(|e| ReadError::Format(e.to_string()))?;
+        if pdf.page_errors > 0 {
+            eprintln!(
+                "Warning: {} of {} pages in {:?} could not be read",
+                pdf.page_errors, pdf.pages, path
+            );
+        }
+        Ok(pdf.text)
+    }
+}
+
+///Reads *.docx files with `extract_text_from_docx_with`.
+pub struct DocxReader {
+    ///How often to retry a file that was not found, see `read_with_retries`.
+    pub retries: u32,
+    ///Also read the comments and the deleted text of tracked changes.
+    pub tracked_changes: bool,
+}
+
+impl Reader for DocxReader {
+    fn extensions(&self) -> &[&str] {
+        &["docx"]
+    }
+
+    fn read(&self, path: &Path) -> Result<String, ReadError> {
+        extract_text_from_docx_with(&read_with_retries(path, self.retries)?, self.tracked_changes)
+    }
+}
+
+///Reads *.odt files with `extract_text_from_odt_with`.
+pub struct OdtReader {
+    ///How often to retry a file that was not found, see `read_with_retries`.
+    pub retries: u32,
+    ///Also read the comments and the deleted text of tracked changes.
+    pub tracked_changes: bool,
+}
+
+impl Reader for OdtReader {
+    fn extensions(&self) -> &[&str] {
+        &["odt"]
+    }
+
+    fn read(&self, path: &Path) -> Result<String, ReadError> {
+        extract_text_from_odt_with(&read_with_retries(path, self.retries)?, self.tracked_changes)
+    }
+}
+
+///Checks if `arg` is an http(s) URL rather than a path.
+pub fn is_url(arg: &str) -> bool {
+    arg.starts_with("http://") || arg.starts_with("https://")
+}
+
+///Name for the results of a URL: its last path segment without extension, or its host, with everything but letters, digits, '-' and '_' replaced by '_'.
+/// # Example
+/// ```
+/// use text_analysis::url_stem;
+/// assert_eq!(url_stem("https://example.com/texts/article.txt"), "article");
+/// assert_eq!(url_stem("https://example.com/"), "example_com");
+/// ```
+pub fn url_stem(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let without_query = without_scheme.split(['?', '#']).next().unwrap_or("");
+    let mut segments = without_query.split('/').filter(|segment| !segment.is_empty());
+    let host = segments.next().unwrap_or("");
+    let stem = match segments.next_back() {
+        Some(segment) => segment.rsplit_once('.').map_or(segment, |(stem, _)| stem),
+        None => host,
+    };
+    stem.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+///Fetch the body of an http(s) URL as text. Needs the feature "url".
+#[cfg(feature = "url")]
+pub fn read_url(url: &str) -> Result<String, ReadError> {
+    let response = ureq::get(url).call().map_err(|e| match e {
+        ureq::Error::Status(status, _) => ReadError::Format(format!("{}: HTTP status {}", url, status)),
+        ureq::Error::Transport(e) => ReadError::Io(std::io::Error::other(e)),
+    })?;
+    Ok(response.into_string()?)
+}
+
+///Readers for all formats supported by the crate. With `tracked_changes` the office readers also read comments and deleted text.
+pub fn default_readers(
+    pdf_strict: bool,
+    retries: u32,
+    invalid_utf8: InvalidUtf8Policy,
+    tracked_changes: bool,
+) -> Vec<Arc<dyn Reader>> {
+    vec![
+        Arc::new(TxtReader {
+            retries,
+            invalid_utf8,
+        }),
+        Arc::new(PdfReader {
+            strict: pdf_strict,
+            retries,
+        }),
+        Arc::new(DocxReader {
+            retries,
+            tracked_changes,
+        }),
+        Arc::new(OdtReader {
+            retries,
+            tracked_changes,
+        }),
+    ]
+}
+
+///Find the reader for the extension of `path` (case-insensitive).
+pub fn find_reader<'a>(readers: &'a [Arc<dyn Reader>], path: &Path) -> Option<&'a Arc<dyn Reader>> {
+    let extension = path.extension().and_then(OsStr::to_str)?.to_lowercase();
+    readers
+        .iter()
+        .find(|reader| reader.extensions().contains(&extension.as_str()))
+}
+
+///Extensions handled by `readers`, e.g. to tell the user which files are supported.
+pub fn supported_extensions(readers: &[Arc<dyn Reader>]) -> Vec<&str> {
+    readers
+        .iter()
+        .flat_map(|reader| reader.extensions().iter().copied())
+        .collect()
+}
+
+//checks if a file is the results file of an earlier run
+fn is_result_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| name.contains("results_word_analysis"))
+}
+
+///Which files `collect_files_with` collects.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CollectOptions {
+    ///Skip files in a directory that are excluded by .gitignore, .ignore or global git excludes.
+    pub respect_gitignore: bool,
+    ///Also collect results files of earlier runs (files containing "results_word_analysis" in their name).
+    ///They are skipped by default, so a second run in the same directory doesn't count the words of the first run's results.
+    pub include_previous_outputs: bool,
+}
+
+///Collect the files to analyze: a single file or all files in a directory with an extension handled by one of `readers`.
+///Doesn't read subdirectories and skips earlier results (files containing "results_word_analysis" in their name).
+pub fn collect_files(path: &Path, readers: &[Arc<dyn Reader>]) -> std::io::Result<Vec<PathBuf>> {
+    collect_files_with(path, readers, &CollectOptions::default())
+}
+
+///Like `collect_files`, but skips files in a directory that are excluded by .gitignore, .ignore or global git excludes.
+///A single file given as `path` is always collected.
+pub fn collect_files_respecting_gitignore(
+    path: &Path,
+    readers: &[Arc<dyn Reader>],
+) -> std::io::Result<Vec<PathBuf>> {
+    let options = CollectOptions {
+        respect_gitignore: true,
+        ..CollectOptions::default()
+    };
+    collect_files_with(path, readers, &options)
+}
+
+///Collect the files to analyze like `collect_files`, with `options`. A single file given as `path` is always collected if one of `readers` handles it.
+pub fn collect_files_with(
+    path: &Path,
+    readers: &[Arc<dyn Reader>],
+    options: &CollectOptions,
+) -> std::io::Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(find_reader(readers, path)
+            .map(|_| vec![path.to_path_buf()])
+            .unwrap_or_default());
+    }
+    let mut documents = Vec::new();
+    for entry in read_dir(path)? {
+        let path = entry?.path();
+        if path.is_file()
+            && (options.include_previous_outputs || !is_result_file(&path))
+            && find_reader(readers, &path).is_some()
+        {
+            documents.push(path);
+        }
+    }
+    documents.sort();
+    if options.respect_gitignore {
+        let mut not_ignored = HashSet::new();
+        for entry in ignore::WalkBuilder::new(path)
+            .max_depth(Some(1))
+            .hidden(false)
+            .require_git(false)
+            .build()
+        {
+            let entry = entry.map_err(std::io::Error::other)?;
+            not_ignored.insert(entry.into_path());
+        }
+        documents.retain(|document| not_ignored.contains(document));
+    }
+    Ok(documents)
+}
+
+///Extensions of the files that `collect_files` skips because none of `readers` handles them, with the number of files for each extension (sorted by extension).
+///Files without extension are counted as "".
+pub fn unsupported_extensions(
+    path: &Path,
+    readers: &[Arc<dyn Reader>],
+) -> std::io::Result<Vec<(String, usize)>> {
+    let files = if path.is_file() {
+        vec![path.to_path_buf()]
+    } else {
+        let mut files = Vec::new();
+        for entry in read_dir(path)? {
+            files.push(entry?.path());
+        }
+        files
+    };
+    let mut extensions: HashMap<String, usize> = HashMap::new();
+    for file in files {
+        if file.is_file() && !is_result_file(&file) && find_reader(readers, &file).is_none() {
+            let extension = file.extension().and_then(OsStr::to_str).unwrap_or("");
+            *extensions.entry(extension.to_lowercase()).or_insert(0) += 1;
+        }
+    }
+    let mut extensions: Vec<(String, usize)> = extensions.into_iter().collect();
+    extensions.sort();
+    Ok(extensions)
+}
+
+///Why there is nothing to analyze at the path given by the user. Each case has its own exit code (see `exit_code`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    ///The path doesn't exist.
+    PathNotFound(PathBuf),
+    ///The path is a file no reader handles. `supported` lists the supported extensions.
+    UnsupportedFile {
+        path: PathBuf,
+        extension: String,
+        supported: Vec<String>,
+    },
+    ///The path is a directory without supported files. `unsupported` counts the other files by extension (see `unsupported_extensions`).
+    EmptyDirectory {
+        path: PathBuf,
+        supported: Vec<String>,
+        unsupported: Vec<(String, usize)>,
+    },
+}
+
+impl InputError {
+    ///Find out why there are no files to analyze at `path` (after `collect_files` found none).
+    /// # Example
+    /// ```
+    /// use text_analysis::{default_readers, InputError, InvalidUtf8Policy};
+    /// let readers = default_readers(false, 0, InvalidUtf8Policy::default(), false);
+    /// let error = InputError::new("does/not/exist".as_ref(), &readers);
+    /// assert_eq!(error, InputError::PathNotFound("does/not/exist".into()));
+    /// assert_eq!(error.exit_code(), 3);
+    /// ```
+    pub fn new(path: &Path, readers: &[Arc<dyn Reader>]) -> InputError {
+        let supported: Vec<String> = supported_extensions(readers).into_iter().map(String::from).collect();
+        if path.is_file() {
+            InputError::UnsupportedFile {
+                path: path.to_path_buf(),
+                extension: path.extension().and_then(OsStr::to_str).unwrap_or("").to_string(),
+                supported,
+            }
+        } else if path.is_dir() {
+            InputError::EmptyDirectory {
+                path: path.to_path_buf(),
+                supported,
+                unsupported: unsupported_extensions(path, readers).unwrap_or_default(),
+            }
+        } else {
+            InputError::PathNotFound(path.to_path_buf())
+        }
+    }
+
+    ///Exit code for the command line: 3 if the path doesn't exist, 4 for an unsupported file, 5 for a directory without supported files.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            InputError::PathNotFound(_) => 3,
+            InputError::UnsupportedFile { .. } => 4,
+            InputError::EmptyDirectory { .. } => 5,
+        }
+    }
+}
+
+//".txt, .pdf" for a list of extensions
+fn dotted(extensions: &[String]) -> String {
+    extensions
+        .iter()
+        .map(|extension| format!(".{}", extension))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+//number of single-character edits between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::PathNotFound(path) => write!(f, "{:?} doesn't exist. Please check the path.", path),
+            InputError::UnsupportedFile {
+                path,
+                extension,
+                supported,
+            } => {
+                write!(
+                    f,
+                    "{:?} is not a supported file (.{}). Supported: {}.",
+                    path,
+                    extension,
+                    dotted(supported)
+                )?;
+                //typos like ".text" or ".pd"
+                let lowercase = extension.to_lowercase();
+                let close: Vec<String> = supported
+                    .iter()
+                    .filter(|supported| edit_distance(&lowercase, supported) == 1)
+                    .cloned()
+                    .collect();
+                if !close.is_empty() {
+                    write!(f, " Did you mean {}?", dotted(&close))?;
+                }
+                Ok(())
+            }
+            InputError::EmptyDirectory {
+                path,
+                supported,
+                unsupported,
+            } => {
+                write!(f, "No supported files ({}) found in {:?}.", dotted(supported), path)?;
+                if !unsupported.is_empty() {
+                    let unsupported: Vec<String> = unsupported
+                        .iter()
+                        .map(|(extension, count)| format!(".{} ({})", extension, count))
+                        .collect();
+                    write!(f, " Skipped unsupported files: {}", unsupported.join(", "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+///Hint if an exclude pattern (see `exclude_matching`) removed no words at all, which usually means the pattern has a typo or doesn't match lowercased words.
+/// # Example
+/// ```
+/// use text_analysis::exclude_pattern_hint;
+/// assert!(exclude_pattern_hint("[A-Z]", 0).is_some());
+/// assert_eq!(exclude_pattern_hint("[0-9]", 12), None);
+/// ```
+pub fn exclude_pattern_hint(pattern: &str, excluded: usize) -> Option<String> {
+    (excluded == 0).then(|| {
+        format!(
+            "the exclude pattern {:?} matched no words; words are lowercased before they are matched",
+            pattern
+        )
+    })
+}
+
+///Hint if the context window (+-`window` words) is larger than the median document, so most words near each word are simply all words of their document.
+/// # Example
+/// ```
+/// use text_analysis::context_window_hint;
+/// assert!(context_window_hint(5, &[3, 4, 100]).is_some());
+/// assert_eq!(context_window_hint(5, &[30, 40, 100]), None);
+/// ```
+pub fn context_window_hint(window: usize, document_lengths: &[usize]) -> Option<String> {
+    if document_lengths.is_empty() {
+        return None;
+    }
+    let mut lengths = document_lengths.to_vec();
+    lengths.sort_unstable();
+    let median = lengths[lengths.len() / 2];
+    (window > median).then(|| {
+        format!(
+            "the context window (+-{} words) is larger than the median document ({} words); words near each word are mostly whole documents",
+            window, median
+        )
+    })
+}
+
+///Time spent in each stage of a run, summed over all files. The stages run one after another, so the sum is close to the total run time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    ///Reading files and extracting their text.
+    pub read: Duration,
+    ///Splitting text into words with `trim_to_words`.
+    pub tokenize: Duration,
+    ///Counting words and collecting the words near each word.
+    pub count: Duration,
+    ///Counting and sorting the words near each word and the frequencies.
+    pub sort: Duration,
+    ///Formatting and writing the results file.
+    pub write: Duration,
+}
+
+impl Timings {
+    ///Run `f` and add the time it took to `stage`.
+    pub fn time<T>(stage: &mut Duration, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        *stage += start.elapsed();
+        result
+    }
+
+    ///Sum of all stages.
+    pub fn total(&self) -> Duration {
+        self.read + self.tokenize + self.count + self.sort + self.write
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "read {:?}, tokenize {:?}, count {:?}, sort {:?}, write {:?}",
+            self.read, self.tokenize, self.count, self.sort, self.write
+        )
+    }
+}
+
+///Word frequencies of a reference corpus, see `parse_reference_frequencies`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReferenceFrequencies {
+    pub counts: HashMap<String, u64>,
+    ///Total number of words in the reference corpus, if given by a `__TOTAL__` row.
+    pub total: Option<u64>,
+}
+
+impl ReferenceFrequencies {
+    ///Add the counts and the total of `other`, e.g. to combine the results files of several runs.
+    ///A list without a `__TOTAL__` row adds the sum of its counts to the total.
+    /// # Example
+    /// ```
+    /// use text_analysis::{parse_reference_frequencies, TokenizerOptions};
+    /// let options = TokenizerOptions::default();
+    /// let mut merged = parse_reference_frequencies("the,60\ncat,2\n__TOTAL__,100\n", &options);
+    /// merged.merge(parse_reference_frequencies("Word: \"cat\", Frequency: 3,\n", &options));
+    /// assert_eq!(merged.counts["cat"], 5);
+    /// assert_eq!(merged.total, Some(103));
+    /// ```
+    pub fn merge(&mut self, other: ReferenceFrequencies) {
+        let total = self.total_or_sum().saturating_add(other.total_or_sum());
+        for (word, count) in other.counts {
+            let merged = self.counts.entry(word).or_insert(0);
+            *merged = merged.saturating_add(count);
+        }
+        self.total = Some(total);
+    }
+
+    ///The total given by the `__TOTAL__` row, or else the sum of all counts.
+    pub fn total_or_sum(&self) -> u64 {
+        self.total
+            .unwrap_or_else(|| self.counts.values().fold(0_u64, |total, count| total.saturating_add(*count)))
+    }
+}
+
+///Parse a reference frequency list. Accepts "word,count" or tab-separated lines as well as the results file of an earlier run ("Word: "word", Frequency: count,").
+///A row with the word `__TOTAL__` gives the total number of words of the reference corpus. Headers and lines that can't be parsed are skipped.
+///Words are lowercased and split with `options` like the analyzed text, so they match its words: "The" counts as "the", and a
+///word split in two ("New-York") adds its count to both.
+/// # Example
+/// ```
+/// use text_analysis::{parse_reference_frequencies, TokenizerOptions};
+/// let reference = parse_reference_frequencies(
+///     "word,count\nthe,60\nThe,5\ncat,2\n__TOTAL__,1000\n",
+///     &TokenizerOptions::default(),
+/// );
+/// assert_eq!(reference.counts["the"], 65);
+/// assert_eq!(reference.total, Some(1000));
+/// ```
+pub fn parse_reference_frequencies(content: &str, options: &TokenizerOptions) -> ReferenceFrequencies {
+    let mut reference = ReferenceFrequencies::default();
+    for line in content.lines() {
+        let (word, count) = match line.strip_prefix("Word: ") {
+            Some(rest) => match rest.split_once(", Frequency: ") {
+                Some((word, count)) => (word.trim_matches('"'), count.trim_end_matches(',')),
+                None => continue,
+            },
+            None => match line.split_once([',', '\t']) {
+                Some((word, count)) => (word.trim().trim_matches('"'), count.trim()),
+                None => continue,
+            },
+        };
+        let count: u64 = match count.trim().parse() {
+            Ok(count) => count,
+            Err(_) => continue,
+        };
+        if word == "__TOTAL__" {
+            reference.total = Some(count);
+            continue;
+        }
+        for word in trim_to_words_with(word, options) {
+            let total = reference.counts.entry(word).or_insert(0);
+            *total = total.saturating_add(count);
+        }
+    }
+    reference
+}
+
+///Format `frequencies` as a "word,count" list with a header and, if known, a `__TOTAL__` row, so `parse_reference_frequencies` can read it again.
+///Words are sorted by `sort`; words with the same count alphabetically.
+/// # Example
+/// ```
+/// use text_analysis::{format_frequency_list, parse_reference_frequencies, ExportSort, TokenizerOptions};
+/// let frequencies = parse_reference_frequencies("cat,2\nthe,60\n__TOTAL__,100\n", &TokenizerOptions::default());
+/// assert_eq!(
+///     format_frequency_list(&frequencies, ExportSort::CountDesc),
+///     "word,count\nthe,60\ncat,2\n__TOTAL__,100\n"
+/// );
+/// ```
+pub fn format_frequency_list(frequencies: &ReferenceFrequencies, sort: ExportSort) -> String {
+    let mut counts: Vec<(&String, &u64)> = frequencies.counts.iter().collect();
+    match sort {
+        ExportSort::CountDesc => counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0))),
+        ExportSort::KeyAsc => counts.sort_by(|a, b| a.0.cmp(b.0)),
+    }
+    let mut to_file = String::from("word,count\n");
+    for (word, count) in counts {
+        to_file.push_str(&format!("{},{}\n", word, count));
+    }
+    if let Some(total) = frequencies.total {
+        to_file.push_str(&format!("__TOTAL__,{}\n", total));
+    }
+    to_file
+}
+
+///Keyness of a word compared to a reference corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyness {
+    pub word: String,
+    pub frequency: u32,
+    pub reference_frequency: u64,
+    ///Log-likelihood (G²) of the difference between both corpora.
+    pub log_likelihood: f64,
+    ///Binary log of the ratio of relative frequencies. Positive if the word is more frequent than in the reference. Zero counts are taken as 0.5.
+    pub log_ratio: f64,
+    ///Probability of a log-likelihood at least this high if both corpora used the word equally often (chi-square with 1 degree of freedom).
+    pub p_value: f64,
+}
+
+///Compare every word in `frequency` against a reference corpus with `reference_total` words. Sorted by log-likelihood, highest first.
+pub fn keyness(
+    frequency: &HashMap<String, u32>,
+    reference: &HashMap<String, u64>,
+    reference_total: u64,
+) -> Vec<Keyness> {
+    let total: f64 = frequency.values().map(|&count| count as f64).sum();
+    let reference_total = reference_total as f64;
+    let mut keyness: Vec<Keyness> = frequency
+        .iter()
+        .map(|(word, &count)| {
+            let reference_count = reference.get(word).copied().unwrap_or(0);
+            let (a, b) = (count as f64, reference_count as f64);
+            let expected_a = total * (a + b) / (total + reference_total);
+            let expected_b = reference_total * (a + b) / (total + reference_total);
+            let mut log_likelihood = 0.0;
+            if a > 0.0 {
+                log_likelihood += a * (a / expected_a).ln();
+            }
+            if b > 0.0 {
+                log_likelihood += b * (b / expected_b).ln();
+            }
+            let log_ratio = ((a.max(0.5) / total) / (b.max(0.5) / reference_total)).log2();
+            Keyness {
+                word: word.clone(),
+                frequency: count,
+                reference_frequency: reference_count,
+                log_likelihood: 2.0 * log_likelihood,
+                log_ratio,
+                p_value: chi_square_p_value(2.0 * log_likelihood),
+            }
+        })
+        .collect();
+    keyness.sort_by(|a, b| {
+        b.log_likelihood
+            .total_cmp(&a.log_likelihood)
+            .then_with(|| a.word.cmp(&b.word))
+    });
+    keyness
+}
+
+///P-value of a chi-square `statistic` with 1 degree of freedom, e.g. a log-likelihood (G²): erfc(sqrt(statistic / 2)).
+///erfc is approximated (Abramowitz and Stegun 7.1.26) with an absolute error below 2e-7.
+/// # Example
+/// ```
+/// use text_analysis::chi_square_p_value;
+/// assert!((chi_square_p_value(3.841) - 0.05).abs() < 1e-4);
+/// assert_eq!(chi_square_p_value(0.0), 1.0);
+/// ```
+pub fn chi_square_p_value(statistic: f64) -> f64 {
+    if statistic <= 0.0 {
+        return 1.0;
+    }
+    let x = (statistic / 2.0).sqrt();
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polynomial = t
+        * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    (polynomial * (-x * x).exp()).clamp(0.0, 1.0)
+}
+
+///Correction for testing the significance of many words at once, see `significant_keyness`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Correction {
+    ///Compare each p-value with the threshold as it is.
+    None,
+    ///Bonferroni: compare each p-value times the number of words with the threshold. Strict.
+    Bonferroni,
+    ///Benjamini-Hochberg: keep the `k` smallest p-values for the largest `k` with p <= k / number of words * threshold (default).
+    #[default]
+    BenjaminiHochberg,
+}
+
+///Keep only the words whose keyness is significant at `threshold` (e.g. 0.01) after `correction`. Returns them in the same order and the number of words removed.
+pub fn significant_keyness(
+    keyness: Vec<Keyness>,
+    threshold: f64,
+    correction: Correction,
+) -> (Vec<Keyness>, usize) {
+    let words = keyness.len() as f64;
+    let cutoff = match correction {
+        Correction::None => threshold,
+        Correction::Bonferroni => threshold / words,
+        Correction::BenjaminiHochberg => {
+            let mut p_values: Vec<f64> = keyness.iter().map(|keyness| keyness.p_value).collect();
+            p_values.sort_by(f64::total_cmp);
+            p_values
+                .iter()
+                .enumerate()
+                .rev()
+                .find(|(rank, p_value)| **p_value <= (*rank as f64 + 1.0) / words * threshold)
+                .map_or(-1.0, |(_, p_value)| *p_value)
+        }
+    };
+    let before = keyness.len();
+    let significant: Vec<Keyness> = keyness
+        .into_iter()
+        .filter(|keyness| keyness.p_value <= cutoff)
+        .collect();
+    let removed = before - significant.len();
+    (significant, removed)
+}
+
+///Decimals of the floats in the results files unless a precision is given (p-values get one more).
+pub const DEFAULT_PRECISION: usize = 3;
+
+///Format a float with `precision` decimals. Formatting doesn't depend on locale, and "-0.000" is written as "0.000" so rounding can't change the output between platforms.
+///NaN is an internal error and is rejected instead of being written to a results file.
+/// # Example
+/// ```
+/// use text_analysis::format_float;
+/// assert_eq!(format_float(2.0_f64.ln(), 3).unwrap(), "0.693");
+/// assert_eq!(format_float(-0.0001, 3).unwrap(), "0.000");
+/// assert!(format_float(f64::NAN, 3).is_err());
+/// ```
+pub fn format_float(value: f64, precision: usize) -> Result<String, String> {
+    if value.is_nan() {
+        return Err("internal error: score is NaN".to_string());
+    }
+    let formatted = format!("{:.*}", precision, value);
+    match formatted.strip_prefix('-') {
+        Some(unsigned) if unsigned.chars().all(|c| c == '0' || c == '.') => Ok(unsigned.to_string()),
+        _ => Ok(formatted),
+    }
+}
+
+///What the results file lists for each of the words near a word.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ContextValues {
+    ///How often the word occurs near the word (default).
+    #[default]
+    Count,
+    ///The count divided by the sum of all counts in the word's "Words near" list.
+    ///The probabilities of each word's list sum to 1.
+    Probability,
+}
+
+///Format the word frequencies and the words near each word for the results file.
+pub fn format_results(counted: &[(String, u32)], map_near: &HashMap<String, Vec<(String, u32)>>) -> String {
+    format_results_with(counted, map_near, ContextValues::Count, None)
+        .expect("counts are written without floats")
+}
+
+///Format the word frequencies and the words near each word for the results file, listing `context_values` for the words near.
+///Probabilities are written with `precision` or three decimals (see `format_float`).
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use text_analysis::{format_results_with, ContextValues};
+/// let counted = vec![("cat".to_string(), 2)];
+/// let mut map_near = HashMap::new();
+/// map_near.insert("cat".to_string(), vec![("the".to_string(), 3), ("sat".to_string(), 1)]);
+/// assert_eq!(
+///     format_results_with(&counted, &map_near, ContextValues::Probability, None).unwrap(),
+///     "Word: \"cat\", Frequency: 2,\n Words near: [(\"the\", 0.750), (\"sat\", 0.250)]\n\n"
+/// );
+/// ```
+pub fn format_results_with(
+    counted: &[(String, u32)],
+    map_near: &HashMap<String, Vec<(String, u32)>>,
+    context_values: ContextValues,
+    precision: Option<usize>,
+) -> Result<String, String> {
+    let precision = precision.unwrap_or(DEFAULT_PRECISION);
+    let mut to_file = String::new();
+    for (word, frequency) in counted {
+        let words_near = &map_near[word];
+        let words_near = match context_values {
+            ContextValues::Count => format!("{:?}", words_near),
+            ContextValues::Probability => {
+                let total: u64 = words_near.iter().map(|(_, count)| u64::from(*count)).sum();
+                let probabilities = words_near
+                    .iter()
+                    .map(|(near, count)| {
+                        let probability = f64::from(*count) / total as f64;
+                        Ok(format!("({:?}, {})", near, format_float(probability, precision)?))
+                    })
+                    .collect::<Result<Vec<String>, String>>()?;
+                format!("[{}]", probabilities.join(", "))
+            }
+        };
+        let combined = format!(
+            "Word: {:?}, Frequency: {:?},\n Words near: {}\n\n",
+            word, frequency, words_near
+        );
+        to_file.push_str(&combined);
+    }
+    Ok(to_file)
+}
+
+///Order of the words in the results files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExportSort {
+    ///Most frequent (or, for keyness, most key) first, as returned by `sort_map_to_vec` and `keyness` (default).
+    #[default]
+    CountDesc,
+    ///Alphabetically by word, for tools that merge sorted files.
+    KeyAsc,
+}
+
+///Sort `counted` for export: unchanged for `ExportSort::CountDesc`, by word for `ExportSort::KeyAsc`.
+/// # Example
+/// ```
+/// use text_analysis::{sort_for_export, ExportSort};
+/// let mut counted = vec![("the".to_string(), 3), ("cat".to_string(), 1)];
+/// sort_for_export(&mut counted, ExportSort::KeyAsc);
+/// assert_eq!(counted, vec![("cat".to_string(), 1), ("the".to_string(), 3)]);
+/// ```
+pub fn sort_for_export(counted: &mut [(String, u32)], sort: ExportSort) {
+    if sort == ExportSort::KeyAsc {
+        counted.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+}
+
+///Format the keyness of each word for the keyness results file, with `precision` decimals or, by default, three (four for the p-value).
+pub fn format_keyness(keyness: &[Keyness], precision: Option<usize>) -> Result<String, String> {
+    let mut to_file = String::new();
+    for keyness in keyness {
+        to_file.push_str(&format!(
+            "Word: {:?}, Frequency: {}, Reference frequency: {}, Log-likelihood: {}, Log ratio: {}, p-value: {}\n",
+            keyness.word,
+            keyness.frequency,
+            keyness.reference_frequency,
+            format_float(keyness.log_likelihood, precision.unwrap_or(DEFAULT_PRECISION))?,
+            format_float(keyness.log_ratio, precision.unwrap_or(DEFAULT_PRECISION))?,
+            format_float(keyness.p_value, precision.unwrap_or(DEFAULT_PRECISION + 1))?
+        ));
+    }
+    Ok(to_file)
+}
+
+///Line endings of the results files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NewlineKind {
+    ///"\n" (default).
+    #[default]
+    Lf,
+    ///"\r\n", for Windows tools that show "\n" files as a single line.
+    CrLf,
+}
+
+///Convert the "\n" line endings of a formatted results file to `newline`.
+///Words are written with escapes (`"a\tb"`), so tabs or newlines inside a word can't break a record across lines.
+/// # Example
+/// ```
+/// use text_analysis::{with_newlines, NewlineKind};
+/// assert_eq!(with_newlines("a\nb\n".to_string(), NewlineKind::CrLf), "a\r\nb\r\n");
+/// ```
+pub fn with_newlines(to_file: String, newline: NewlineKind) -> String {
+    match newline {
+        NewlineKind::Lf => to_file,
+        NewlineKind::CrLf => to_file.replace('\n', "\r\n"),
+    }
+}
+
+///save file to path. Return result.
+pub fn save_file(to_file: String, path: PathBuf) -> std::io::Result<PathBuf> {
+    save_file_named(to_file, path, "results_word_analysis")
+}
+
+///save file to path as [date/time]_`name`.txt. Return result.
+pub fn save_file_named(to_file: String, path: PathBuf, name: &str) -> std::io::Result<PathBuf> {
+    save_file_stamped(to_file, path, name, &timestamp(TimestampKind::LocalSeconds))
+}
+
+///Format of the timestamp results files start with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampKind {
+    ///Local time with second precision, e.g. `2024_05_01_14_03_59` (default).
+    #[default]
+    LocalSeconds,
+    ///UTC with millisecond precision, e.g. `2024_05_01_12_03_59_123Z`. Sorts the same on machines in different time zones.
+    UtcMillis,
+    ///UTC with millisecond precision, the process id and a counter, e.g. `2024_05_01_12_03_59_123Z_4242_0`. Unique even for runs started in the same millisecond.
+    RunId,
+}
+
+//counts the run ids created by this process
+static RUN_ID_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+///Timestamp of `kind` for the current time. Take it once per run and pass it to `save_file_stamped` so all results files of a run share it.
+///If the environment variable `SOURCE_DATE_EPOCH` is set to a number of seconds since 1970 (the convention for reproducible builds),
+///that time is used instead of the current time, so the results files get the same names on every run.
+pub fn timestamp(kind: TimestampKind) -> String {
+    let now = source_date_epoch().unwrap_or_else(Utc::now);
+    match kind {
+        TimestampKind::LocalSeconds => now.with_timezone(&Local).format("%Y_%m_%d_%H_%M_%S").to_string(),
+        TimestampKind::UtcMillis => now.format("%Y_%m_%d_%H_%M_%S_%3fZ").to_string(),
+        TimestampKind::RunId => format!(
+            "{}_{}_{}",
+            now.format("%Y_%m_%d_%H_%M_%S_%3fZ"),
+            std::process::id(),
+            RUN_ID_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    }
+}
+
+//the time set in SOURCE_DATE_EPOCH, if any
+fn source_date_epoch() -> Option<DateTime<Utc>> {
+    let seconds = std::env::var("SOURCE_DATE_EPOCH").ok()?.trim().parse().ok()?;
+    DateTime::from_timestamp(seconds, 0)
+}
+
+//counts the probe files created by this process
+static PROBE_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+///Check that results files can be created in `dir` by creating and removing a small probe file. Call it before a long analysis so it fails right away.
+///The probe has a name unique to the process and is not a *.txt file, so it can't clobber or be mistaken for a document.
+pub fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(
+        ".text_analysis_probe_{}_{}",
+        std::process::id(),
+        PROBE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    //if the probe can't be created, there is nothing to remove (and a file of that name isn't ours)
+    let mut file = OpenOptions::new().write(true).create_new(true).open(&probe)?;
+    let written = file.write_all(b"probe");
+    drop(file);
+    let removed = std::fs::remove_file(&probe);
+    written.and(removed)
+}
+
+///save file to path as `timestamp`_`name`.txt. Return result.
+pub fn save_file_stamped(
+    to_file: String,
+    mut path: PathBuf,
+    name: &str,
+    timestamp: &str,
+) -> std::io::Result<PathBuf> {
+    let new_filename: String = format!("{}_{}.txt", timestamp, name);
+    path.push(new_filename);
+
+    let file = OpenOptions::new().write(true).create(true).truncate(true).open(&path)?;
+
+    write_or_remove(file, &path, to_file.as_bytes(), name)?;
+
+    Ok(path)
+}
+
+//writes `content` to `writer`, which writes to the file at `path`. If writing fails (e.g. the disk is full) the partial file is removed
+//and the error names the results file, its path and how many bytes were written before it failed.
+fn write_or_remove<W: Write>(mut writer: W, path: &Path, content: &[u8], name: &str) -> std::io::Result<()> {
+    let mut written = 0;
+    let result = loop {
+        if written == content.len() {
+            break writer.flush();
+        }
+        match writer.write(&content[written..]) {
+            Ok(0) => break Err(ErrorKind::WriteZero.into()),
+            Ok(n) => written += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(e) => break Err(e),
+        }
+    };
+    result.map_err(|e| {
+        drop(writer);
+        let _ = std::fs::remove_file(path);
+        std::io::Error::new(
+            e.kind(),
+            format!(
+                "writing {} to {:?} failed after {} of {} bytes, partial file removed: {}",
+                name,
+                path,
+                written,
+                content.len(),
+                e
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Deref;
+
+    //a new empty directory for one test, removed when the test ends (also if it panics)
+    struct TestDir(PathBuf);
+
+    impl Deref for TestDir {
+        type Target = PathBuf;
+
+        fn deref(&self) -> &PathBuf {
+            &self.0
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn test_dir(name: &str) -> TestDir {
+        let path = std::env::temp_dir().join(format!("text_analysis_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TestDir(path)
+    }
+    #[test]
+    fn test_count() {
+        let words = vec![
+            "one".to_string(),
+            "two".to_string(),
+            "two".to_string(),
+            "three".to_string(),
+            "three".to_string(),
+            "three".to_string(),
+        ];
+        let counted = count_words(&words);
+        let mut words_map = HashMap::new();
+        words_map.insert("one".to_string(), 1_u32);
+        words_map.insert("two".to_string(), 2_u32);
+        words_map.insert("three".to_string(), 3_u32);
+        assert_eq!(counted, words_map);
+    }
+
+    #[test]
+    fn test_segment_sentences() {
+        let text = "Dr. Smith met Mr. Jones. Did they talk?! \"Yes.\" They discussed e.g. the weather \n  and J. Doe";
+        let spans = segment_sentences(text, &TokenizerOptions::default());
+        let sentences: Vec<&str> = spans
+            .iter()
+            .map(|span| &text[span.byte_start..span.byte_end])
+            .collect();
+        assert_eq!(
+            sentences,
+            vec![
+                "Dr. Smith met Mr. Jones.",
+                "Did they talk?!",
+                "\"Yes.\"",
+                "They discussed e.g. the weather \n  and J. Doe"
+            ]
+        );
+        let tokens: Vec<(usize, usize)> = spans
+            .iter()
+            .map(|span| (span.token_start, span.token_end))
+            .collect();
+        assert_eq!(tokens, vec![(0, 5), (5, 8), (8, 9), (9, 17)]);
+        assert_eq!(trim_to_words(text.to_string()).len(), 17);
+
+        let text = "Introduction\n \nFirst sentence.\nSecond one?";
+        let sentences: Vec<&str> = segment_sentences(text, &TokenizerOptions::default())
+            .iter()
+            .map(|span| &text[span.byte_start..span.byte_end])
+            .collect();
+        assert_eq!(
+            sentences,
+            vec!["Introduction", "First sentence.", "Second one?"]
+        );
+    }
+
+    #[test]
+    fn test_read_with_retries() {
+        let dir = test_dir("retries");
+        let missing = dir.join("missing.txt");
+        let error = read_with_retries(&missing, 1).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+
+        //file shows up while the first retry is waiting
+        let late = dir.join("late.txt");
+        let writer = {
+            let late = late.clone();
+            thread::spawn(move || {
+                thread::sleep(READ_RETRY_BACKOFF / 4);
+                std::fs::write(late, "arrived late").unwrap();
+            })
+        };
+        assert_eq!(read_with_retries(&late, 2).unwrap(), b"arrived late");
+        writer.join().unwrap();
+    }
+
+    struct Rot13Reader;
+
+    impl Reader for Rot13Reader {
+        fn extensions(&self) -> &[&str] {
+            &["rot13"]
+        }
+
+        fn read(&self, path: &Path) -> Result<String, ReadError> {
+            let text = std::fs::read_to_string(path)?;
+            Ok(text
+                .chars()
+                .map(|c| match c {
+                    'a'..='m' | 'A'..='M' => (c as u8 + 13) as char,
+                    'n'..='z' | 'N'..='Z' => (c as u8 - 13) as char,
+                    _ => c,
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_custom_reader() {
+        let dir = test_dir("reader");
+        std::fs::write(dir.join("secret.ROT13"), "uryyb jbeyq uryyb").unwrap();
+        std::fs::write(dir.join("plain.txt"), "hello").unwrap();
+        std::fs::write(dir.join("ignored.md"), "hello").unwrap();
+
+        let mut readers = default_readers(false, 0, InvalidUtf8Policy::Fail, false);
+        readers.push(Arc::new(Rot13Reader));
+        let documents = collect_files(&dir, &readers).unwrap();
+        assert_eq!(documents, vec![dir.join("plain.txt"), dir.join("secret.ROT13")]);
+        assert_eq!(
+            unsupported_extensions(&dir, &readers).unwrap(),
+            vec![("md".to_string(), 1)]
+        );
+        assert!(collect_files(&dir.join("ignored.md"), &readers).unwrap().is_empty());
+
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        for document in &documents {
+            let text = find_reader(&readers, document).unwrap().read(document).unwrap();
+            count_words_near(&trim_to_words(text), &mut frequency, &mut words_near_vec_map);
+        }
+        assert_eq!(frequency["hello"], 3);
+        assert_eq!(frequency["world"], 1);
+        assert_eq!(words_near_vec_map["world"], vec!["hello", "hello"]);
+    }
+
+    #[test]
+    fn test_timings() {
+        let mut timings = Timings::default();
+        let words = Timings::time(&mut timings.tokenize, || {
+            thread::sleep(Duration::from_millis(5));
+            trim_to_words("timed words".to_string())
+        });
+        assert_eq!(words.len(), 2);
+        assert!(timings.tokenize >= Duration::from_millis(5));
+        assert_eq!(timings.total(), timings.tokenize);
+        assert!(timings.to_string().starts_with("read 0ns, tokenize "));
+
+        //a whole run: every stage takes some time and together they take no longer than the run
+        let dir = test_dir("timings");
+        std::fs::write(dir.join("text.txt"), "the cat sat on the mat. ".repeat(2000)).unwrap();
+        let readers = default_readers(false, 0, InvalidUtf8Policy::Fail, false);
+        let start = Instant::now();
+        let mut timings = Timings::default();
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        for document in collect_files(&dir, &readers).unwrap() {
+            let reader = find_reader(&readers, &document).unwrap();
+            let text = Timings::time(&mut timings.read, || reader.read(&document)).unwrap();
+            let words = Timings::time(&mut timings.tokenize, || trim_to_words(text));
+            Timings::time(&mut timings.count, || {
+                count_words_near(&words, &mut frequency, &mut words_near_vec_map)
+            });
+        }
+        let (counted, map_near) = Timings::time(&mut timings.sort, || {
+            let map_near: HashMap<String, Vec<(String, u32)>> = words_near_vec_map
+                .into_iter()
+                .map(|(word, words)| (word, sort_map_to_vec(count_words(&words))))
+                .collect();
+            (sort_map_to_vec(frequency), map_near)
+        });
+        Timings::time(&mut timings.write, || {
+            save_file(format_results(&counted, &map_near), dir.to_path_buf()).unwrap()
+        });
+        let wall_clock = start.elapsed();
+        for stage in [timings.read, timings.tokenize, timings.count, timings.sort, timings.write] {
+            assert!(stage > Duration::ZERO, "{}", timings);
+        }
+        assert!(timings.total() <= wall_clock, "{} > {:?}", timings, wall_clock);
+    }
+
+    #[test]
+    fn test_keyness() {
+        let options = TokenizerOptions::default();
+        let reference = parse_reference_frequencies(
+            "term\tcount\nthe\t50\ncat\t1\ndog\t49\n__TOTAL__\t100\n",
+            &options,
+        );
+        assert_eq!(reference.total, Some(100));
+        let previous_run = parse_reference_frequencies(
+            "Word: \"the\", Frequency: 50,\n Words near: [(\"cat\", 1)]\n\n",
+            &options,
+        );
+        assert_eq!(previous_run.counts["the"], 50);
+        //reference words are lowercased and split like the analyzed words
+        let mixed_case = parse_reference_frequencies("The,30\nthe,20\nCAT's,1\nNew-York,2\n", &options);
+        let mut counts: Vec<(&str, u64)> =
+            mixed_case.counts.iter().map(|(word, count)| (word.as_str(), *count)).collect();
+        counts.sort();
+        assert_eq!(counts, vec![("cat", 1), ("new", 2), ("the", 50), ("york", 2)]);
+        let turkish = TokenizerOptions {
+            turkish_casing: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(parse_reference_frequencies("IŞIK,3\n", &turkish).counts["ışık"], 3);
+
+        let mut frequency = HashMap::new();
+        frequency.insert("the".to_string(), 50_u32);
+        frequency.insert("cat".to_string(), 49_u32);
+        frequency.insert("dog".to_string(), 1_u32);
+        let keyness = keyness(&frequency, &reference.counts, reference.total.unwrap());
+        let cat = keyness.iter().find(|k| k.word == "cat").unwrap();
+        let the = keyness.iter().find(|k| k.word == "the").unwrap();
+        assert!(cat.log_ratio > 5.0);
+        assert!(the.log_ratio.abs() < 1e-9);
+        assert!(the.log_likelihood.abs() < 1e-9);
+        assert_eq!(keyness.last().unwrap().word, "the");
+        //G² = 2 * (49 ln(49/25) + 1 ln(1/25))
+        let expected = 2.0 * (49.0 * (49.0_f64 / 25.0).ln() + (1.0_f64 / 25.0).ln());
+        assert!((cat.log_likelihood - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_results_golden() {
+        let text = "The cat sat on the mat. The dog sat on the cat!".to_string();
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        count_words_near(&trim_to_words(text), &mut frequency, &mut words_near_vec_map);
+        let mut map_near = HashMap::new();
+        for (word, words) in words_near_vec_map {
+            map_near.insert(word, sort_map_to_vec(count_words(&words)));
+        }
+        let mut reference = HashMap::new();
+        reference.insert("the".to_string(), 6_u64);
+        reference.insert("dog".to_string(), 1_u64);
+        let keyness_file = format_keyness(&keyness(&frequency, &reference, 100), None).unwrap();
+        let results_file = format_results(&sort_map_to_vec(frequency), &map_near);
+
+        //ties are ordered alphabetically, so the files are identical on every run
+        let expected_results = "\
+Word: \"the\", Frequency: 4,
+ Words near: [(\"sat\", 6), (\"the\", 6), (\"on\", 5), (\"cat\", 4), (\"dog\", 3), (\"mat\", 3)]
+
+Word: \"cat\", Frequency: 2,
+ Words near: [(\"the\", 4), (\"on\", 2), (\"sat\", 2), (\"dog\", 1), (\"mat\", 1)]
+
+Word: \"on\", Frequency: 2,
+ Words near: [(\"the\", 6), (\"cat\", 2), (\"dog\", 2), (\"mat\", 2), (\"sat\", 2)]
+
+Word: \"sat\", Frequency: 2,
+ Words near: [(\"the\", 6), (\"on\", 3), (\"cat\", 2), (\"mat\", 2), (\"dog\", 1)]
+
+Word: \"dog\", Frequency: 1,
+ Words near: [(\"the\", 3), (\"on\", 2), (\"sat\", 2), (\"cat\", 1), (\"mat\", 1)]
+
+Word: \"mat\", Frequency: 1,
+ Words near: [(\"the\", 3), (\"on\", 2), (\"sat\", 2), (\"cat\", 1), (\"dog\", 1)]
+
+";
+        let expected_keyness = "\
+Word: \"cat\", Frequency: 2, Reference frequency: 0, Log-likelihood: 8.934, Log ratio: 5.059, p-value: 0.0028
+Word: \"on\", Frequency: 2, Reference frequency: 0, Log-likelihood: 8.934, Log ratio: 5.059, p-value: 0.0028
+Word: \"sat\", Frequency: 2, Reference frequency: 0, Log-likelihood: 8.934, Log ratio: 5.059, p-value: 0.0028
+Word: \"the\", Frequency: 4, Reference frequency: 6, Log-likelihood: 5.768, Log ratio: 2.474, p-value: 0.0163
+Word: \"mat\", Frequency: 1, Reference frequency: 0, Log-likelihood: 4.467, Log ratio: 4.059, p-value: 0.0346
+Word: \"dog\", Frequency: 1, Reference frequency: 1, Log-likelihood: 1.921, Log ratio: 3.059, p-value: 0.1657
+";
+        assert_eq!(results_file, expected_results);
+        assert_eq!(keyness_file, expected_keyness);
+    }
+
+    #[test]
+    fn test_significant_keyness() {
+        let text = "The cat sat on the mat. The dog sat on the cat!".to_string();
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        count_words_near(&trim_to_words(text), &mut frequency, &mut words_near_vec_map);
+        let mut reference = HashMap::new();
+        reference.insert("the".to_string(), 6_u64);
+        reference.insert("dog".to_string(), 1_u64);
+        let keyness = keyness(&frequency, &reference, 100);
+        //G² = 8.934 for 2 of 12 words against 0 of 100
+        assert!((keyness[0].p_value - 0.002799).abs() < 1e-4);
+        assert!((chi_square_p_value(6.635) - 0.01).abs() < 1e-4);
+        assert!((chi_square_p_value(10.828) - 0.001).abs() < 1e-4);
+
+        let words = |keyness: &[Keyness]| -> Vec<String> {
+            keyness.iter().map(|keyness| keyness.word.clone()).collect()
+        };
+        let (kept, removed) = significant_keyness(keyness.clone(), 0.01, Correction::None);
+        assert_eq!((words(&kept), removed), (vec!["cat".to_string(), "on".to_string(), "sat".to_string()], 3));
+        let (kept, removed) = significant_keyness(keyness.clone(), 0.01, Correction::Bonferroni);
+        assert_eq!((kept.len(), removed), (0, 6));
+        let (kept, removed) = significant_keyness(keyness.clone(), 0.01, Correction::BenjaminiHochberg);
+        assert_eq!((kept.len(), removed), (3, 3));
+        let (kept, removed) = significant_keyness(keyness, 0.05, Correction::BenjaminiHochberg);
+        assert_eq!(words(&kept), vec!["cat", "on", "sat", "the", "mat"]);
+        assert_eq!(removed, 1);
+    }
+
+    #[test]
+    fn test_context_probabilities() {
+        let text = "The cat sat on the mat. The dog sat on the cat!".to_string();
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        count_words_near(&trim_to_words(text), &mut frequency, &mut words_near_vec_map);
+        let mut map_near = HashMap::new();
+        for (word, words) in words_near_vec_map {
+            map_near.insert(word, sort_map_to_vec(count_words(&words)));
+        }
+        let results_file = format_results_with(
+            &sort_map_to_vec(frequency),
+            &map_near,
+            ContextValues::Probability,
+            None,
+        )
+        .unwrap();
+
+        assert!(results_file.starts_with(
+            "Word: \"the\", Frequency: 4,\n Words near: [(\"sat\", 0.222), (\"the\", 0.222), (\"on\", 0.185),"
+        ));
+        for line in results_file.lines().filter(|line| line.starts_with(" Words near: ")) {
+            let sum: f64 = line
+                .split(", ")
+                .filter_map(|value| value.trim_end_matches([')', ']']).parse::<f64>().ok())
+                .sum();
+            assert!((sum - 1.0).abs() < 0.01, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_docx_end_to_end() {
+        use std::io::Cursor;
+        use zip::write::FileOptions;
+        let dir = test_dir("docx");
+        let mut docx = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        docx.start_file("word/document.xml", FileOptions::default()).unwrap();
+        docx.write_all(
+            br#"<w:document><w:body><w:p><w:r><w:t>Office</w:t></w:r><w:r><w:tab/><w:t>text</w:t></w:r></w:p><w:p><w:del><w:r><w:delText>removed</w:delText><w:t>gone</w:t></w:r></w:del><w:r><w:t>office</w:t></w:r></w:p></w:body></w:document>"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("report.docx"), docx.finish().unwrap().into_inner()).unwrap();
+
+        let readers = default_readers(false, 0, InvalidUtf8Policy::Fail, false);
+        let documents = collect_files(&dir, &readers).unwrap();
+        assert_eq!(documents, vec![dir.join("report.docx")]);
+        let text = find_reader(&readers, &documents[0]).unwrap().read(&documents[0]).unwrap();
+        let mut frequency = HashMap::new();
+        count_words_near(&trim_to_words(text), &mut frequency, &mut HashMap::new());
+        assert_eq!(frequency["office"], 2);
+        assert_eq!(frequency["text"], 1);
+        assert_eq!(frequency.len(), 2);
+    }
+
+    #[test]
+    fn test_keep_identifiers() {
+        let text = "See parse_config_file in version 1.2.3. Done.";
+        assert_eq!(
+            trim_to_words(text.to_string()),
+            vec!["see", "parseconfigfile", "in", "version", "123", "done"]
+        );
+        let options = TokenizerOptions {
+            keep_identifiers: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(
+            trim_to_words_with(text, &options),
+            vec!["see", "parse_config_file", "in", "version", "1.2.3", "done"]
+        );
+    }
+
+    //writer that fails after `remaining` bytes, like a disk running full
+    struct FailAfter<W: Write> {
+        inner: W,
+        remaining: usize,
+    }
+
+    impl<W: Write> Write for FailAfter<W> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(std::io::Error::other("no space left on device"));
+            }
+            let n = self.inner.write(&buf[..buf.len().min(self.remaining)])?;
+            self.remaining -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    #[test]
+    fn test_check_writable() {
+        let dir = test_dir("writable");
+        check_writable(&dir).unwrap();
+        assert_eq!(std::fs::read_dir(dir.as_path()).unwrap().count(), 0);
+        std::fs::remove_dir(dir.as_path()).unwrap();
+        assert!(check_writable(&dir).is_err());
+    }
+
+    #[test]
+    fn test_run_log() {
+        let dir = test_dir("log");
+        let path = dir.join("log.jsonl");
+        let log = RunLog::open(&path).unwrap();
+        log.log_file("a \"quoted\"\tname.txt", Some(120), None).unwrap();
+        let warning = FileWarning {
+            path: PathBuf::from("b.txt"),
+            kind: WarningKind::Unreadable,
+            message: "line1\nline2".to_string(),
+        };
+        log.log_file("b.txt", None, Some(&warning)).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"time\":\""));
+        assert!(lines[0].ends_with(",\"file\":\"a \\\"quoted\\\"\\tname.txt\",\"words\":120}"));
+        assert!(lines[1].ends_with(
+            ",\"file\":\"b.txt\",\"warning\":\"unreadable\",\"message\":\"line1\\nline2\"}"
+        ));
+    }
+
+    #[test]
+    fn test_input_error() {
+        let readers = default_readers(false, 0, InvalidUtf8Policy::default(), false);
+        let dir = test_dir("input");
+        std::fs::write(dir.join("notes.text"), "text").unwrap();
+        std::fs::write(dir.join("UPPER.TXT"), "text").unwrap();
+
+        let missing = InputError::new(&dir.join("missing"), &readers);
+        assert_eq!(missing.exit_code(), 3);
+        let unsupported = InputError::new(&dir.join("notes.text"), &readers);
+        assert_eq!(unsupported.exit_code(), 4);
+        assert!(unsupported
+            .to_string()
+            .ends_with("is not a supported file (.text). Supported: .txt, .pdf, .docx, .odt. Did you mean .txt?"));
+        //upper case extensions are supported
+        assert_eq!(
+            collect_files(&dir.join("UPPER.TXT"), &readers).unwrap(),
+            vec![dir.join("UPPER.TXT")]
+        );
+        std::fs::remove_file(dir.join("UPPER.TXT")).unwrap();
+        let empty = InputError::new(&dir, &readers);
+        assert_eq!(empty.exit_code(), 5);
+        assert_eq!(
+            empty.to_string(),
+            format!(
+                "No supported files (.txt, .pdf, .docx, .odt) found in {:?}. Skipped unsupported files: .text (1)",
+                dir.as_path()
+            )
+        );
+    }
+
+    #[test]
+    fn test_bom() {
+        let dir = test_dir("bom");
+        std::fs::write(dir.join("bom.txt"), "\u{FEFF}Word one. Word two.").unwrap();
+        std::fs::write(dir.join("plain.txt"), "Word one. Word two.").unwrap();
+        for invalid_utf8 in [InvalidUtf8Policy::Fail, InvalidUtf8Policy::Replace] {
+            let reader = TxtReader {
+                retries: 0,
+                invalid_utf8,
+            };
+            let bom = reader.read(&dir.join("bom.txt")).unwrap();
+            let plain = reader.read(&dir.join("plain.txt")).unwrap();
+            assert_eq!(
+                word_frequencies(&bom, &TokenizerOptions::default()),
+                word_frequencies(&plain, &TokenizerOptions::default())
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_or_remove() {
+        let dir = test_dir("write");
+        let path = dir.join("results.txt");
+        let file = std::fs::File::create(&path).unwrap();
+        let writer = FailAfter { inner: file, remaining: 10 };
+        let error = write_or_remove(writer, &path, &[b'a'; 25], "results_word_analysis").unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("results_word_analysis"));
+        assert!(message.contains(&format!("{:?}", path)));
+        assert!(message.contains("after 10 of 25 bytes"));
+        assert!(message.contains("no space left on device"));
+        assert!(!path.exists());
+
+        let mut buffer = Vec::new();
+        write_or_remove(&mut buffer, &path, b"complete", "results_word_analysis").unwrap();
+        assert_eq!(buffer, b"complete");
+    }
+
+    #[test]
+    fn test_collect_files_respecting_gitignore() {
+        let dir = test_dir("gitignore");
+        std::fs::write(dir.join(".gitignore"), "generated_*.txt\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "kept").unwrap();
+        std::fs::write(dir.join("generated_1.txt"), "ignored").unwrap();
+        let readers = default_readers(false, 0, InvalidUtf8Policy::Fail, false);
+
+        let all = collect_files(&dir, &readers).unwrap();
+        assert_eq!(all, vec![dir.join("generated_1.txt"), dir.join("notes.txt")]);
+        let respected = collect_files_respecting_gitignore(&dir, &readers).unwrap();
+        assert_eq!(respected, vec![dir.join("notes.txt")]);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        //checks `stamp` against `pattern`, where '9' stands for any digit
+        fn matches(stamp: &str, pattern: &str) -> bool {
+            stamp.len() == pattern.len()
+                && stamp
+                    .chars()
+                    .zip(pattern.chars())
+                    .all(|(c, p)| if p == '9' { c.is_ascii_digit() } else { c == p })
+        }
+        assert!(matches(&timestamp(TimestampKind::LocalSeconds), "9999_99_99_99_99_99"));
+        assert!(matches(&timestamp(TimestampKind::UtcMillis), "9999_99_99_99_99_99_999Z"));
+
+        let first = timestamp(TimestampKind::RunId);
+        let second = timestamp(TimestampKind::RunId);
+        assert_ne!(first, second);
+        let (time, rest) = first.split_at(24);
+        assert!(matches(time, "9999_99_99_99_99_99_999Z"));
+        let (pid, counter) = rest[1..].split_once('_').unwrap();
+        assert_eq!(pid, std::process::id().to_string());
+        assert!(counter.parse::<u32>().is_ok());
+
+        let dir = test_dir("timestamp");
+        let path = save_file_stamped(String::new(), dir.clone(), "test_timestamp", "2024_05_01").unwrap();
+        assert_eq!(path, dir.join("2024_05_01_test_timestamp.txt"));
+    }
+
+    #[test]
+    fn test_vocabulary_growth() {
+        let mut growth = VocabularyGrowth::new(3);
+        growth.add(&trim_to_words("the cat sat on".to_string()));
+        growth.add(&trim_to_words("the mat the cat".to_string()));
+        assert_eq!(growth.points(), vec![(3, 3), (6, 5), (8, 5)]);
+        assert_eq!(
+            format_vocabulary_growth(&growth.points()),
+            "tokens_seen, types_seen\n3, 3\n6, 5\n8, 5\n"
+        );
+        assert!(VocabularyGrowth::new(1000).points().is_empty());
+    }
+
+    #[test]
+    fn test_count_words_near_within() {
+        let content_vec = trim_to_words("a b c d e f".to_string());
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        count_words_near_within(&content_vec, &mut frequency, &mut words_near_vec_map, 2);
+        assert_eq!(words_near_vec_map["a"], vec!["b", "c"]);
+        assert_eq!(words_near_vec_map["c"], vec!["a", "b", "d", "e"]);
+        assert_eq!(words_near_vec_map["f"], vec!["d", "e"]);
+        assert_eq!(frequency["d"], 1);
+
+        let mut words_near_vec_map = HashMap::new();
+        count_words_near_within(&content_vec, &mut frequency, &mut words_near_vec_map, 0);
+        assert!(words_near_vec_map["c"].is_empty());
+
+        //the default window is 5 words before and 4 after, a window of 5 is 5 on each side
+        let content_vec = trim_to_words("a b c d e f g h i j k".to_string());
+        let mut default_map = HashMap::new();
+        count_words_near(&content_vec, &mut frequency, &mut default_map);
+        assert_eq!(default_map["f"], vec!["a", "b", "c", "d", "e", "g", "h", "i", "j"]);
+        let mut within_map = HashMap::new();
+        count_words_near_within(&content_vec, &mut frequency, &mut within_map, 5);
+        assert_eq!(within_map["f"], vec!["a", "b", "c", "d", "e", "g", "h", "i", "j", "k"]);
+    }
+
+    #[test]
+    fn test_example_sentences() {
+        let text = "One cat. Two cats and a cat. The cat sleeps. A dog.";
+        let mut examples = ExampleSentences::new(1, 50, 7);
+        examples.add(text, "a.txt", &TokenizerOptions::default());
+        assert_eq!(examples.get("cat"), vec![("a.txt", "The cat sleeps.")]);
+        assert_eq!(examples.get("bird"), vec![]);
+
+        let mut all = ExampleSentences::new(5, 50, 7);
+        all.add(text, "a.txt", &TokenizerOptions::default());
+        assert_eq!(all.get("cat").len(), 3);
+        let counted = sort_map_to_vec(word_frequencies(text, &TokenizerOptions::default()));
+        assert_eq!(
+            format_examples(&all, &counted, 1),
+            "Word: \"cat\", Source: \"a.txt\", Example: \"One cat.\"\n\
+             Word: \"cat\", Source: \"a.txt\", Example: \"Two cats and a cat.\"\n\
+             Word: \"cat\", Source: \"a.txt\", Example: \"The cat sleeps.\"\n"
+        );
+
+        //only the samples of frequent words are kept
+        let mut bounded = ExampleSentences::new(2, 1, 7);
+        for i in 0..100 {
+            let text = format!("The word{} is here. The end.", i);
+            bounded.add(&text, &format!("{}.txt", i), &TokenizerOptions::default());
+            assert!(bounded.samples.len() <= 4);
+        }
+        assert_eq!(bounded.get("the").len(), 2);
+        assert!(bounded.get("word0").is_empty());
+        assert_eq!(bounded.sources.len(), 100);
+    }
+
+    #[test]
+    fn test_sentence_lengths() {
+        let options = TokenizerOptions::default();
+        let mut histogram = HashMap::new();
+        add_sentence_lengths("The cat sat. The dog sat on the mat! Why?", &options, &mut histogram);
+        add_sentence_lengths("Dr. Smith sat on the mat.\n\nNo", &options, &mut histogram);
+        assert_eq!(histogram.len(), 3);
+        assert_eq!((histogram[&1], histogram[&3], histogram[&6]), (2, 1, 2));
+        let (sentences, mean, std_dev) = sentence_length_stats(&histogram);
+        assert_eq!(sentences, 5);
+        assert!((mean - 3.4).abs() < 1e-9);
+        assert!((std_dev - 2.24499).abs() < 1e-4);
+        assert_eq!(
+            format_sentence_lengths(&histogram, None).unwrap(),
+            "Sentences: 5, Mean length: 3.400, Standard deviation: 2.245\n\nlength, sentences\n1, 2\n3, 1\n6, 2\n"
+        );
+
+        //lengths are counted in the words of the tokenizer used for the analysis
+        let unicode_words = TokenizerOptions {
+            kind: TokenizerKind::UnicodeWords,
+            ..Default::default()
+        };
+        let mut histogram = HashMap::new();
+        add_sentence_lengths("東京 タワー. Don't go.", &options, &mut histogram);
+        assert_eq!(histogram, HashMap::from([(2, 2)]));
+        let mut histogram = HashMap::new();
+        add_sentence_lengths("東京 タワー. Don't go.", &unicode_words, &mut histogram);
+        assert_eq!(histogram, HashMap::from([(3, 1), (2, 1)]));
+        assert_eq!(sentence_length_stats(&HashMap::new()), (0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_light_normalization() {
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        let text = "Cats and a cat. Species of boxes, a box, studies, one study, analyses and an analysis. Dogs.";
+        count_words_near(&trim_to_words(text.to_string()), &mut frequency, &mut words_near_vec_map);
+
+        let remap = light_normalize_map(&frequency, 1);
+        let mut merged: Vec<(&str, &str)> = remap.iter().map(|(variant, base)| (variant.as_str(), base.as_str())).collect();
+        merged.sort();
+        assert_eq!(
+            merged,
+            vec![("analyses", "analysis"), ("boxes", "box"), ("cats", "cat"), ("studies", "study")]
+        );
+        //"cat" occurs only once
+        assert!(light_normalize_map(&frequency, 2).is_empty());
+
+        //short words and words that only look like plurals stay
+        let mut function_words = HashMap::new();
+        let text = "It is what it is: I read the news, its new news, as I told us and a u.";
+        count_words_near(&trim_to_words(text.to_string()), &mut function_words, &mut HashMap::new());
+        assert!(["i", "it", "new", "a", "u"].iter().all(|word| function_words.contains_key(*word)));
+        assert!(light_normalize_map(&function_words, 1).is_empty());
+        let mut look_like_plurals = HashMap::new();
+        let text = "Your book is yours, our goods are ours, her good car is hers; sometimes a sometime friend.";
+        count_words_near(&trim_to_words(text.to_string()), &mut look_like_plurals, &mut HashMap::new());
+        assert!(["your", "our", "her", "good", "sometime"].iter().all(|word| look_like_plurals.contains_key(*word)));
+        assert!(light_normalize_map(&look_like_plurals, 1).is_empty());
+
+        apply_light_normalization(&remap, &mut frequency, &mut words_near_vec_map);
+        assert_eq!(frequency["cat"], 2);
+        assert_eq!(frequency["species"], 1);
+        assert_eq!(frequency["dogs"], 1);
+        assert!(!frequency.contains_key("cats"));
+        assert!(!words_near_vec_map.contains_key("cats"));
+        assert_eq!(words_near_vec_map["cat"].iter().filter(|word| *word == "and").count(), 2);
+        assert!(words_near_vec_map.values().flatten().all(|word| !remap.contains_key(word)));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let dir = test_dir("utf8");
+        let path = dir.join("mojibake.txt");
+        std::fs::write(&path, b"na\xefve text").unwrap();
+        let reader = |invalid_utf8| TxtReader {
+            retries: 0,
+            invalid_utf8,
+        };
+
+        assert!(matches!(reader(InvalidUtf8Policy::Fail).read(&path), Err(ReadError::Format(_))));
+        assert!(matches!(reader(InvalidUtf8Policy::Skip).read(&path), Err(ReadError::InvalidUtf8(_))));
+        let text = reader(InvalidUtf8Policy::Replace).read(&path).unwrap();
+        assert_eq!(trim_to_words(text), vec!["na\u{FFFD}ve", "text"]);
+        assert_eq!(decode_utf8_lossy(&std::fs::read(&path).unwrap()).1, 1);
+    }
+
+    #[test]
+    fn test_counts_saturate() {
+        let content_vec = trim_to_words("big big small".to_string());
+        let mut frequency = HashMap::new();
+        frequency.insert("big".to_string(), u32::MAX - 1);
+        let mut words_near_vec_map = HashMap::new();
+        count_words_near(&content_vec, &mut frequency, &mut words_near_vec_map);
+        assert_eq!(frequency["big"], u32::MAX);
+        count_words_near_within(&content_vec, &mut frequency, &mut words_near_vec_map, 1);
+        assert_eq!(frequency["big"], u32::MAX);
+        assert_eq!(frequency["small"], 2);
+
+        let mut remap = HashMap::new();
+        remap.insert("bigs".to_string(), "big".to_string());
+        frequency.insert("bigs".to_string(), 5);
+        apply_light_normalization(&remap, &mut frequency, &mut words_near_vec_map);
+        assert_eq!(frequency["big"], u32::MAX);
+
+        let reference =
+            parse_reference_frequencies(&format!("the,{}\nthe,1\n", u64::MAX), &TokenizerOptions::default());
+        assert_eq!(reference.counts["the"], u64::MAX);
+    }
+
+    #[test]
+    fn test_previous_outputs() {
+        let dir = test_dir("previous");
+        let text = dir.join("text.txt");
+        std::fs::write(&text, "the cat sat").unwrap();
+        let readers = default_readers(false, 0, InvalidUtf8Policy::Fail, false);
+
+        //first run
+        let documents = collect_files(&dir, &readers).unwrap();
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        for document in &documents {
+            let words = trim_to_words(find_reader(&readers, document).unwrap().read(document).unwrap());
+            count_words_near(&words, &mut frequency, &mut words_near_vec_map);
+        }
+        let mut map_near = HashMap::new();
+        for (word, words) in words_near_vec_map {
+            map_near.insert(word, sort_map_to_vec(count_words(&words)));
+        }
+        let results = save_file(format_results(&sort_map_to_vec(frequency), &map_near), dir.clone()).unwrap();
+
+        //second run in the same directory doesn't read the results of the first
+        assert_eq!(collect_files(&dir, &readers).unwrap(), vec![text.clone()]);
+        let options = CollectOptions {
+            include_previous_outputs: true,
+            ..CollectOptions::default()
+        };
+        let mut with_previous = vec![results, text];
+        with_previous.sort();
+        assert_eq!(collect_files_with(&dir, &readers, &options).unwrap(), with_previous);
+    }
+
+    #[test]
+    fn test_newlines() {
+        let counted = vec![("tab\tword".to_string(), 1), ("line\nword".to_string(), 1)];
+        let mut map_near = HashMap::new();
+        map_near.insert("tab\tword".to_string(), vec![("line\nword".to_string(), 1)]);
+        map_near.insert("line\nword".to_string(), vec![("tab\tword".to_string(), 1)]);
+        let to_file = with_newlines(format_results(&counted, &map_near), NewlineKind::CrLf);
+        assert_eq!(
+            to_file,
+            "Word: \"tab\\tword\", Frequency: 1,\r\n Words near: [(\"line\\nword\", 1)]\r\n\r\n\
+             Word: \"line\\nword\", Frequency: 1,\r\n Words near: [(\"tab\\tword\", 1)]\r\n\r\n"
+        );
+        assert!(!to_file.contains('\t'));
+        assert_eq!(to_file.matches('\n').count(), to_file.matches("\r\n").count());
+    }
+
+    #[test]
+    fn test_turkish_casing() {
+        let text = "İSTANBUL'da IRMAK ılık İzmir".to_string();
+        assert_eq!(
+            trim_to_words(text.clone()),
+            vec!["i\u{307}stanbulda", "irmak", "ılık", "i\u{307}zmir"]
+        );
+        let options = TokenizerOptions {
+            turkish_casing: true,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(
+            trim_to_words_with(&text, &options),
+            vec!["istanbulda", "ırmak", "ılık", "izmir"]
+        );
+    }
+
+    #[test]
+    fn test_emoji() {
+        let text = "Family 👨‍👩‍👧 time👨‍👩‍👧! Thumbs 👍🏽 and 👍 ❤️";
+        let options = |kind, emoji| TokenizerOptions {
+            kind,
+            emoji,
+            ..TokenizerOptions::default()
+        };
+        for kind in [TokenizerKind::Whitespace, TokenizerKind::UnicodeWords] {
+            let kept = word_frequencies(text, &options(kind, Some(EmojiPolicy::Keep)));
+            assert_eq!(kept["👨‍👩‍👧"], 2);
+            assert_eq!((kept["time"], kept["👍🏽"], kept["👍"], kept["❤️"]), (1, 1, 1, 1));
+            assert_eq!(kept.len(), 8);
+            let bucketed = word_frequencies(text, &options(kind, Some(EmojiPolicy::Bucket)));
+            assert_eq!(bucketed["<emoji>"], 5);
+            assert_eq!(bucketed.len(), 5);
+            assert_eq!(
+                trim_to_words_with(text, &options(kind, Some(EmojiPolicy::Drop))),
+                vec!["family", "time", "thumbs", "and"]
+            );
+        }
+        //without a policy the whitespace tokenizer keeps emoji in their words, unicode words drops them
+        let words = trim_to_words_with("time👍 ok", &TokenizerOptions::default());
+        assert_eq!(words, vec!["time👍", "ok"]);
+        let words = trim_to_words_with("time👍 ok", &options(TokenizerKind::UnicodeWords, None));
+        assert_eq!(words, vec!["time", "ok"]);
+    }
+
+    #[test]
+    fn test_unicode_words() {
+        let text = "Don't stop.Start 東京タワー, v1.2.3 snake_case".to_string();
+        assert_eq!(
+            trim_to_words(text.clone()),
+            vec!["dont", "stopstart", "東京タワー", "v123", "snakecase"]
+        );
+        let options = TokenizerOptions {
+            kind: TokenizerKind::UnicodeWords,
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(
+            trim_to_words_with(&text, &options),
+            vec!["don't", "stop.start", "東", "京", "タワー", "v1.2.3", "snake_case"]
+        );
+    }
+
+    #[test]
+    fn test_export_sort() {
+        let words = trim_to_words("the cat and the dog saw the bird".to_string());
+        let mut frequency = HashMap::new();
+        let mut words_near_vec_map = HashMap::new();
+        count_words_near(&words, &mut frequency, &mut words_near_vec_map);
+        let mut map_near: HashMap<String, Vec<(String, u32)>> = words_near_vec_map
+            .into_iter()
+            .map(|(word, near)| (word, sort_map_to_vec(count_words(&near))))
+            .collect();
+        let mut counted = sort_map_to_vec(frequency);
+        let first_row = |counted: &[(String, u32)], map_near: &HashMap<String, Vec<(String, u32)>>| {
+            format_results(&counted[..1], map_near)
+        };
+        assert_eq!(
+            first_row(&counted, &map_near),
+            "Word: \"the\", Frequency: 3,\n Words near: [(\"the\", 4), (\"and\", 3), (\"cat\", 3), (\"dog\", 3), (\"bird\", 2), (\"saw\", 2)]\n\n"
+        );
+        sort_for_export(&mut counted, ExportSort::KeyAsc);
+        for near in map_near.values_mut() {
+            sort_for_export(near, ExportSort::KeyAsc);
+        }
+        assert_eq!(
+            first_row(&counted, &map_near),
+            "Word: \"and\", Frequency: 1,\n Words near: [(\"cat\", 1), (\"dog\", 1), (\"saw\", 1), (\"the\", 3)]\n\n"
+        );
+    }
+
+    #[test]
+    fn test_surface_forms() {
+        let text = "Apple's APPLE apple. İstanbul ISTANBUL well-known Sooo sooo";
+        let options = TokenizerOptions {
+            turkish_casing: true,
+            collapse_repeats: Some(2),
+            ..TokenizerOptions::default()
+        };
+        let mut forms = HashMap::new();
+        add_surface_forms(text, &options, &mut forms);
+        let counted = sort_map_to_vec(word_frequencies(text, &options));
+        let mut words: Vec<&String> = forms.keys().collect();
+        words.sort();
+        let mut expected: Vec<&String> = counted.iter().map(|(word, _)| word).collect();
+        expected.sort();
+        assert_eq!(words, expected);
+        assert_eq!(
+            format_surface_forms(&counted[..2], &forms, ExportSort::CountDesc),
+            "Word: \"apple\", Surface forms: [(\"APPLE\", 1), (\"Apple\", 1), (\"apple\", 1)]\n\
+             Word: \"soo\", Surface forms: [(\"Soo\", 1), (\"soo\", 1)]\n"
+        );
+        let mut cased = HashMap::new();
+        add_surface_forms("the The the Cat cat", &TokenizerOptions::default(), &mut cased);
+        let mut counted = sort_map_to_vec(word_frequencies("the The the Cat cat", &TokenizerOptions::default()));
+        assert_eq!(
+            format_surface_forms(&counted, &cased, ExportSort::CountDesc),
+            "Word: \"the\", Surface forms: [(\"the\", 2), (\"The\", 1)]\n\
+             Word: \"cat\", Surface forms: [(\"Cat\", 1), (\"cat\", 1)]\n"
+        );
+        sort_for_export(&mut counted, ExportSort::KeyAsc);
+        assert_eq!(
+            format_surface_forms(&counted, &cased, ExportSort::KeyAsc),
+            "Word: \"cat\", Surface forms: [(\"Cat\", 1), (\"cat\", 1)]\n\
+             Word: \"the\", Surface forms: [(\"The\", 1), (\"the\", 2)]\n"
+        );
+        assert_eq!(forms["istanbul"].len(), 1);
+        assert_eq!(forms["ıstanbul"]["ISTANBUL"], 1);
+    }
+
+    #[test]
+    fn test_suspect_text_cjk() {
+        let options = TokenizerOptions {
+            kind: TokenizerKind::UnicodeWords,
+            ..TokenizerOptions::default()
+        };
+        let japanese = trim_to_words_with(&"東京は日本の首都です。".repeat(20), &options);
+        assert!(japanese.len() >= SuspectThresholds::default().min_words);
+        assert_eq!(suspect_text(&japanese, &SuspectThresholds::default()), None);
+        //letter-by-letter Latin text next to the Japanese is still found
+        let mixed = trim_to_words_with(&"東京は日本の首都です。T h e c a t s a t. ".repeat(20), &options);
+        assert!(suspect_text(&mixed, &SuspectThresholds::default())
+            .unwrap()
+            .contains("single-character words"));
+    }
+
+    #[test]
+    fn test_hints() {
+        assert_eq!(
+            exclude_pattern_hint("^[A-Z]+$", 0).unwrap(),
+            "the exclude pattern \"^[A-Z]+$\" matched no words; words are lowercased before they are matched"
+        );
+        assert_eq!(exclude_pattern_hint("^[a-z]+$", 1), None);
+        //median of an even number of documents is the upper middle one
+        assert!(context_window_hint(5, &[100, 2, 4, 3]).is_some());
+        assert_eq!(context_window_hint(5, &[100, 2, 5, 6]), None);
+        assert_eq!(context_window_hint(5, &[]), None);
+    }
+
+    #[test]
+    fn test_precision() {
+        let mut frequency = HashMap::new();
+        frequency.insert("cat".to_string(), 2_u32);
+        frequency.insert("dog".to_string(), 1_u32);
+        let mut reference = HashMap::new();
+        reference.insert("dog".to_string(), 3_u64);
+        let keyness = keyness(&frequency, &reference, 10);
+        assert_eq!(
+            format_keyness(&keyness[..1], Some(2)).unwrap(),
+            "Word: \"cat\", Frequency: 2, Reference frequency: 0, Log-likelihood: 5.87, Log ratio: 3.74, p-value: 0.02\n"
+        );
+        assert_eq!(
+            format_keyness(&keyness[..1], Some(8)).unwrap(),
+            "Word: \"cat\", Frequency: 2, Reference frequency: 0, Log-likelihood: 5.86534828, Log ratio: 3.73696559, p-value: 0.01544180\n"
+        );
+
+        let mut map_near = HashMap::new();
+        map_near.insert("cat".to_string(), vec![("dog".to_string(), 2), ("cat".to_string(), 1)]);
+        let counted = vec![("cat".to_string(), 2)];
+        assert_eq!(
+            format_results_with(&counted, &map_near, ContextValues::Probability, Some(2)).unwrap(),
+            "Word: \"cat\", Frequency: 2,\n Words near: [(\"dog\", 0.67), (\"cat\", 0.33)]\n\n"
+        );
+        let mut histogram = HashMap::new();
+        histogram.insert(2, 2);
+        histogram.insert(3, 1);
+        assert!(format_sentence_lengths(&histogram, Some(8))
+            .unwrap()
+            .starts_with("Sentences: 3, Mean length: 2.33333333, Standard deviation: 0.47140452\n"));
+    }
+
+    #[test]
+    fn test_max_min_index() {
+        let index1 = 5;
+        let min_index1 = get_index_min(&index1);
+        let max_index1 = get_index_max(&index1, &9);
+        assert_eq!(min_index1, 0);
+        assert_eq!(max_index1, 9);
+        let index2 = 0;
+        let min_index2 = get_index_min(&index2);
+        let max_index2 = get_index_max(&index2, &5);
+        assert_eq!(min_index2, 0);
+        assert_eq!(max_index2, 5);
+        let index3 = 100;
+        let min_index3 = get_index_min(&index3);
+        let max_index3 = get_index_max(&index3, &103);
+        assert_eq!(min_index3, 95);
+        assert_eq!(max_index3, 103);
+    }
+
+    //builds a PDF with one page per entry; `None` is a broken page object lacking MediaBox and content
+    fn build_pdf(pages: &[Option<&str>]) -> Vec<u8> {
+        use pdf_extract::{Dictionary, Document, Object, Stream};
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let mut font = Dictionary::new();
+        font.set("Type", Object::Name(b"Font".to_vec()));
+        font.set("Subtype", Object::Name(b"Type1".to_vec()));
+        font.set("BaseFont", Object::Name(b"Helvetica".to_vec()));
+        let font_id = doc.add_object(font);
+        let mut fonts = Dictionary::new();
+        fonts.set("F1", font_id);
+        let mut resources = Dictionary::new();
+        resources.set("Font", fonts);
+        let resources_id = doc.add_object(resources);
+        let mut kids = Vec::new();
+        for text in pages {
+            let mut page = Dictionary::new();
+            page.set("Type", Object::Name(b"Page".to_vec()));
+            page.set("Parent", pages_id);
+            if let Some(text) = text {
+                let content = format!("BT /F1 12 Tf 72 712 Td ({}) Tj ET", text);
+                let contents_id = doc.add_object(Stream::new(Dictionary::new(), content.into_bytes()));
+                page.set("Contents", contents_id);
+                page.set("MediaBox", vec![0.into(), 0.into(), 595.into(), 842.into()]);
+            }
+            kids.push(Object::from(doc.add_object(page)));
+        }
+        let mut pages_dict = Dictionary::new();
+        pages_dict.set("Type", Object::Name(b"Pages".to_vec()));
+        pages_dict.set("Count", pages.len() as i64);
+        pages_dict.set("Resources", resources_id);
+        pages_dict.set("Kids", kids);
+        doc.objects.insert(pages_id, Object::Dictionary(pages_dict));
+        let mut catalog = Dictionary::new();
+        catalog.set("Type", Object::Name(b"Catalog".to_vec()));
+        catalog.set("Pages", pages_id);
+        let catalog_id = doc.add_object(catalog);
+        doc.trailer.set("Root", catalog_id);
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_pdf_broken_page() {
+        let bytes = build_pdf(&[Some("first page"), None, Some("third page")]);
+        let pdf = extract_pdf_text(&bytes, false).unwrap();
+        assert_eq!(pdf.pages, 3);
+        assert_eq!(pdf.page_errors, 1);
+        let words = trim_to_words(pdf.text);
+        assert!(words.contains(&"first".to_string()));
+        assert!(words.contains(&"third".to_string()));
+
+        assert!(extract_pdf_text(&bytes, true).is_err());
+        assert!(extract_pdf_text(&build_pdf(&[None, None]), false).is_err());
     }
 
     #[test]
@@ -217,7 +3382,7 @@ mod tests {
 
         words_near_vec_map
             .entry(word.to_owned())
-            .or_insert_with(Vec::new)
+            .or_default()
             .append(&mut words_near_vec);
     }
 
@@ -1,67 +1,274 @@
 //! # Text_Analysis
-//! Analyze text stored as *.txt in provided file or directory. Doesn't read files in subdirectories.
+//! Analyze text stored as *.txt, *.pdf, *.docx or *.odt in provided file or directory. Doesn't read files in subdirectories.
 //! Counting all words and then searching for every unique word in the vicinity (+-5 words).
-//! Stores results in file [date/time]results_word_analysis.txt in given directory.
+//! Stores results in file [date/time]results_word_analysis.txt in given directory. Stops before reading any files if results can't be saved there.
+//! If saving results fails anyway (e.g. the disk is full), the number of words and the most frequent words are printed before the error.
 //! ## Usage: ```text_analysis path/to/directory_or_file```
+//! The subcommand ```analyze``` is the default and can be left out; all options below belong to it.
+//! ```text_analysis report path/to/directory_or_file``` takes the same options, saves no files and prints a JSON report of the run.
+//! ```text_analysis merge results_1.txt results_2.txt [--output merged.csv]``` adds up the word counts of results files into a "word,count" list.
+//! Exits with code 3 if the path doesn't exist, 4 if it is an unsupported file and 5 if the directory has no supported files.
+//! Pages of a PDF that can't be read are skipped with a warning. Use ```--pdf-strict``` to skip the whole PDF instead.
+//! Use ```--tracked-changes``` to also analyze comments and deleted text of tracked changes in *.docx and *.odt files.
+//! Use ```--verbose``` to print how long reading, tokenizing, counting, sorting and writing took.
+//! Use ```--keep-identifiers``` to keep words like ```snake_case_name``` or ```1.2.3``` intact.
+//! Use ```--turkish-casing``` to lowercase Turkish text correctly ("İ" -> "i", "I" -> "ı").
+//! Use ```--collapse-repeats N``` to shorten runs of the same letter to N, e.g. with 2 "soooo" counts as "soo".
+//! Use ```--tokenizer unicode-words``` to split words at Unicode word boundaries, e.g. for text in scripts without spaces. It drops emoji and symbols.
+//! Use ```--emoji keep``` to count each emoji as a word, ```--emoji bucket``` to count them all as "<emoji>" or ```--emoji drop``` to remove them.
+//! Use ```--exclude-pattern REGEX``` to drop words matching a regular expression, e.g. ```'[0-9]'```.
+//! Files that disappear between scanning the directory and reading them are retried once; use ```--read-retries N``` to change the number of retries.
+//! Text files with invalid UTF-8 are read with U+FFFD in place of the invalid bytes and a warning; use ```--invalid-utf8 fail``` or ```skip``` instead.
+//! Use ```--light-normalize``` to merge plurals like "cats" into "cat" when both occur.
+//! Add ```--light-normalize-min-count N``` to only merge into base forms that occur at least N times (default 1).
+//! Use ```--reference-freq path/to/list.csv``` to compare word frequencies against a reference corpus (see README).
+//! Add ```--significance 0.01``` to keep only significant words (```--correction none|bonferroni|bh```, default bh); both need ```--reference-freq```.
+//! Use ```--context-window N``` to collect the words within +-N of each word instead (the default is 5 before and 4 after).
+//! Use ```--context-values probability``` to list the share of each word near a word instead of its count.
+//! Use ```--sort key``` to list words alphabetically in the results files instead of most frequent first.
+//! Use ```--precision N``` to write all decimal numbers with N decimals.
+//! Use ```--newline crlf``` to write results files with Windows line endings.
+//! Use ```--timestamp utc-ms``` (or ```run-id```) to start results file names with UTC time in milliseconds instead of local time in seconds. ```SOURCE_DATE_EPOCH``` (seconds since 1970) fixes the time used.
+//! Use ```--no-files``` to only print the number of words and the 20 most frequent words (and the ```--rarest``` words) instead of saving any results files.
+//! Use ```--surface-forms``` to also save how each word is capitalized in the text ("Apple", "apple", "APPLE") and how often.
+//! Use ```--examples K``` to also save up to K example sentences for each of the 50 most frequent words.
+//! Use ```--rarest N``` to also save the N least frequent words (often typos).
+//! Use ```--sentence-lengths``` to also save the distribution of sentence lengths in words.
+//! Use ```--vocabulary-growth``` to also save the number of distinct words seen after every 1000 words.
+//! Use ```--respect-gitignore``` to skip files excluded by .gitignore or .ignore files.
+//! Files whose text looks like a failed extraction (mostly single letters or undecodable characters) are reported; use ```--drop-suspect``` to skip them.
+//! Use ```--log-file path/to/log.jsonl``` to append one JSON line per file (words analyzed, warnings) as each file is done.
+//! Results files of earlier runs are skipped; use ```--include-previous-outputs``` to analyze them as well.
+//! With the feature "url", an http(s) URL can be given instead of a path; results are saved in the current directory.
+//! Hints about options that probably don't do what was intended (e.g. an exclude pattern that matched nothing) are printed at the end; use ```--quiet``` to hide them.
+//! Use ```--version-info``` to print the version, supported formats and dependency versions (for bug reports).
+
+mod cli;
 
 use std::collections::HashMap;
 use std::env::args;
-use std::ffi::OsStr;
-use std::fs::read_dir;
-use std::fs::File;
-use std::io::prelude::Read;
-use std::panic;
 use std::path::PathBuf;
 use std::time::Instant;
 
 use text_analysis::{
-    count_words, get_index_max, get_index_min, save_file, sort_map_to_vec, trim_to_words,
+    add_sentence_lengths, add_surface_forms, apply_light_normalization, bottom_n, check_writable,
+    collect_files_with, context_window_hint, count_words, count_words_near, count_words_near_within,
+    default_readers, exclude_matching, exclude_pattern_hint, find_reader, format_examples,
+    format_frequency_list, format_keyness, format_rarest, format_results_with, format_run_report,
+    format_sentence_lengths, format_summary, format_surface_forms, format_vocabulary_growth, is_url,
+    keyness, light_normalize_map, parse_reference_frequencies, save_file_stamped,
+    significant_keyness, sort_for_export, sort_map_to_vec, supported_extensions, suspect_text,
+    timestamp, trim_to_words_with, url_stem, with_newlines, DEFAULT_CONTEXT_WINDOW,
+    ExampleSentences, ExportSort, FileWarning, InputError, InvalidUtf8Policy, ReadError,
+    ReferenceFrequencies, RunLog, SuspectThresholds, Timings, VocabularyGrowth, WarningKind,
 };
 
+use cli::{parse_args, AnalyzeOptions, Command, MergeOptions};
+
+//number of words between two points of the vocabulary growth curve
+const VOCABULARY_GROWTH_INTERVAL: usize = 1000;
+
+//times the base form has to occur for --light-normalize to merge a word into it
+const LIGHT_NORMALIZE_MIN_COUNT: u32 = 1;
+
+//number of most frequent words to save example sentences for (--examples)
+const EXAMPLES_TOP_WORDS: usize = 50;
+//seed for picking example sentences, so every run picks the same ones
+const EXAMPLES_SEED: u64 = 0x5EED;
+//number of most frequent words printed with --no-files
+const SUMMARY_TOP_WORDS: usize = 20;
+
+//dependencies doing the actual reading, with the versions in Cargo.lock (see build.rs)
+const DEPENDENCIES: [(&str, &str); 4] = [
+    ("pdf-extract", env!("TEXT_ANALYSIS_VERSION_PDF_EXTRACT")),
+    ("zip", env!("TEXT_ANALYSIS_VERSION_ZIP")),
+    ("quick-xml", env!("TEXT_ANALYSIS_VERSION_QUICK_XML")),
+    ("chrono", env!("TEXT_ANALYSIS_VERSION_CHRONO")),
+];
+
+//print crate version, supported input formats and dependency versions, used for bug reports
+fn print_version_info() {
+    println!("text_analysis {}", env!("CARGO_PKG_VERSION"));
+    let readers = default_readers(false, 0, InvalidUtf8Policy::Fail, false);
+    println!("formats: {}", supported_extensions(&readers).join(", "));
+    println!("url: {}", if cfg!(feature = "url") { "enabled" } else { "disabled" });
+    for (name, version) in DEPENDENCIES {
+        println!("{} {}", name, version);
+    }
+    if cfg!(feature = "url") {
+        println!("ureq {}", env!("TEXT_ANALYSIS_VERSION_UREQ"));
+    }
+}
+
+//something to read text from
+enum Input {
+    Url(String),
+    File(PathBuf),
+}
+
+//fetch the text of a URL given instead of a path
+#[cfg(feature = "url")]
+fn fetch_url(url: &str) -> Result<String, ReadError> {
+    text_analysis::read_url(url)
+}
+
+#[cfg(not(feature = "url"))]
+fn fetch_url(url: &str) -> Result<String, ReadError> {
+    Err(ReadError::Format(format!(
+        "can't read {}: reading URLs needs text_analysis built with the feature \"url\"",
+        url
+    )))
+}
+
+//print why there is nothing to analyze and exit with its exit code
+fn exit_with(error: InputError) -> ! {
+    eprintln!("Error: {}", error);
+    std::process::exit(error.exit_code())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    match parse_args(args().skip(1))? {
+        Command::Analyze(options) => analyze(options, false),
+        Command::Report(options) => analyze(options, true),
+        Command::Merge(options) => merge(options),
+        Command::VersionInfo => {
+            print_version_info();
+            Ok(())
+        }
+    }
+}
+
+//combine the word counts of the results files in `options` into one "word,count" list
+fn merge(options: MergeOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut merged = ReferenceFrequencies::default();
+    for file in &options.files {
+        let content =
+            std::fs::read_to_string(file).map_err(|e| format!("Cannot read {:?}: {}", file, e))?;
+        merged.merge(parse_reference_frequencies(&content, &options.tokenizer_options));
+    }
+    let to_file = format_frequency_list(&merged, options.export_sort);
+    match &options.output {
+        Some(output) => {
+            std::fs::write(output, to_file)?;
+            println!("Merged {} files into {:?}", options.files.len(), output);
+        }
+        None => print!("{}", to_file),
+    }
+    Ok(())
+}
+
+//analyze the path in `options`; with `report` print a JSON report instead of the summary and other messages
+fn analyze(options: AnalyzeOptions, report: bool) -> Result<(), Box<dyn std::error::Error>> {
     let instant = Instant::now();
 
-    //get path or filename from args
-    let path = PathBuf::from(args().nth(1).expect("no file or directory provided"));
+    let AnalyzeOptions {
+        path: path_arg,
+        pdf_strict,
+        tracked_changes,
+        verbose,
+        collect_options,
+        context_values,
+        timestamp_kind,
+        vocabulary_growth,
+        context_window,
+        sentence_lengths,
+        light_normalize,
+        light_normalize_min_count,
+        exclude_pattern,
+        invalid_utf8,
+        significance,
+        correction,
+        newline,
+        precision,
+        rarest,
+        no_files,
+        quiet,
+        drop_suspect,
+        log_file,
+        export_sort,
+        examples,
+        surface_forms,
+        tokenizer_options,
+        read_retries,
+        reference_freq,
+        reference_total,
+    } = options;
+    //collected while reading, for the optional results files
+    let mut vocabulary_growth =
+        vocabulary_growth.then(|| VocabularyGrowth::new(VOCABULARY_GROWTH_INTERVAL));
+    let mut sentence_lengths: Option<HashMap<usize, usize>> = sentence_lengths.then(HashMap::new);
+    let mut examples =
+        examples.map(|k| ExampleSentences::new(k, EXAMPLES_TOP_WORDS, EXAMPLES_SEED));
+    let mut surface_forms: Option<HashMap<String, HashMap<String, u32>>> =
+        surface_forms.then(HashMap::new);
+    let suspect_thresholds = SuspectThresholds::default();
+    //an http(s) URL is fetched and analyzed instead of reading files
+    let url = is_url(&path_arg).then(|| path_arg.clone());
+    let path = PathBuf::from(path_arg);
 
-    //print path/file provided to stdout
-    println!("path or file: {:?}", path);
+    //print path/file provided to stdout; the report has it in its JSON
+    if !report {
+        println!("path or file: {:?}", path);
+    }
+
+    //reference corpus to compare word frequencies against, with its total from --reference-total, __TOTAL__ row or sum of counts
+    let reference = match &reference_freq {
+        Some(file) => {
+            let reference =
+                parse_reference_frequencies(&std::fs::read_to_string(file)?, &tokenizer_options);
+            let total = reference_total.unwrap_or_else(|| reference.total_or_sum());
+            //keyness needs words in the reference corpus; fail before reading anything
+            if total == 0 {
+                return Err(format!(
+                    "The reference corpus {:?} has 0 words in total; check the file or --reference-total",
+                    file
+                )
+                .into());
+            }
+            Some((reference, total))
+        }
+        None => None,
+    };
+
+    //readers for the supported file formats
+    let readers = default_readers(pdf_strict, read_retries, invalid_utf8, tracked_changes);
 
-    //Vec documents will contain filenames of readable files in directory
-    let mut documents = Vec::new();
     //path_dir is the directory to save results file in.
     let mut path_dir: PathBuf = PathBuf::new();
-    //Ckeck if argument is a file and push to Vec documents
-    if path.is_file() {
+    //Ckeck if argument is a URL, a file or a directory
+    if url.is_some() {
+        path_dir.push(".");
+    } else if path.is_file() {
         path_dir.push(
             path.parent()
                 .expect("error parsing path for provided single file"),
         );
-        documents.push(path)
-        //Ckeck if argument is a directory
     } else if path.is_dir() {
         path_dir.push(path.clone());
-        //walk directory and add .txt to Vec documents - TO DO: Add support for pdf and docx files
-        for entry in read_dir(&path).expect("error parsing 'entry in read_dir(&path)'") {
-            let entry = entry.expect("error unwrapping entry");
-            let path = entry.path();
-            if path.is_file()
-                && !path
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .expect("error transforming filename to str")
-                    .contains("results_word_analysis")
-                && path.extension().and_then(OsStr::to_str) == Some("txt")
-                //|| path.extension().and_then(OsStr::to_str) == Some("pdf") //TO DO: Enable pdf
-                //|| path.extension().and_then(OsStr::to_str) == Some("docx") //TO DO: Enable docx
-            {
-                documents.push(path);
-            }
-        }
     } else {
-        panic!("Provided argument is neither directory nor file. Please check.")
+        exit_with(InputError::new(&path, &readers))
+    }
+    //fail before reading anything if the results can't be saved
+    if !no_files {
+        check_writable(&path_dir)
+            .map_err(|e| format!("Cannot save results in {:?}: {}", path_dir, e))?;
+    }
+    //Vec documents will contain filenames of readable files in directory
+    let documents = if url.is_some() {
+        Vec::new()
+    } else {
+        collect_files_with(&path, &readers, &collect_options)?
+    };
+    if collect_options.include_previous_outputs {
+        eprintln!(
+            "Warning: results files of earlier runs in {:?} are analyzed as well; their words will be counted",
+            path
+        );
+    }
+    if documents.is_empty() && url.is_none() {
+        exit_with(InputError::new(&path, &readers))
     }
+
     //prepare Hashmaps to store results
     let mut frequency: HashMap<String, u32> = HashMap::new();
 
@@ -69,100 +276,301 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut map_near: HashMap<String, Vec<(String, u32)>> = HashMap::new();
 
-    //read each file and globally update the HashMap "frequency" (frequency of each word) and HashMap "words_near_vec_map" (with Vec of counted words near each word)
-    for filename in documents {
-        if filename.extension().and_then(OsStr::to_str) == Some("txt") {
-            let mut f: File = File::open(filename).expect("error opening txt-file");
-            let mut text = String::new();
-            f.read_to_string(&mut text).expect("error reading txt-file");
-            let content_vec: Vec<String> = trim_to_words(text);
-            let mut words_near_vec: Vec<String> = Vec::new();
+    //time spent per stage, printed with --verbose
+    let mut timings = Timings::default();
 
-            for (index, word) in content_vec.clone().into_iter().enumerate() {
-                *frequency.entry(word.to_owned()).or_insert(0) += 1;
+    //one line per file in the --log-file
+    let run_log = match &log_file {
+        Some(log_file) => Some(
+            RunLog::open(log_file).map_err(|e| format!("Cannot open log file {:?}: {}", log_file, e))?,
+        ),
+        None => None,
+    };
 
-                let min: usize = get_index_min(&index);
-                let max: usize = get_index_max(&index, &content_vec.len());
+    //for the hints at the end of the run
+    let mut excluded_words = 0;
+    let mut document_lengths: Vec<usize> = Vec::new();
 
-                (for (number, value) in content_vec.iter().enumerate().take(max).skip(min) {
-                    if number == index {
-                        continue;
-                    } else {
-                        //println!("{:?}", content_vec[i]);
-                        words_near_vec.push(value.clone()); //pushes -+5 words to vec
-                    }
-                });
+    //files that were skipped, with the reason
+    let mut warnings: Vec<FileWarning> = Vec::new();
 
-                words_near_vec_map
-                    .entry(word.to_owned())
-                    .or_insert_with(Vec::new)
-                    .append(&mut words_near_vec);
+    //the URL (if given) and the files, read one after another
+    let inputs = url
+        .iter()
+        .map(|url| Input::Url(url.clone()))
+        .chain(documents.into_iter().map(Input::File));
+
+    //read each input and globally update the HashMap "frequency" (frequency of each word) and HashMap "words_near_vec_map" (with Vec of words near each word)
+    for input in inputs {
+        let source = match &input {
+            Input::Url(url) => url.clone(),
+            Input::File(filename) => filename.display().to_string(),
+        };
+        let text = match input {
+            Input::Url(url) => {
+                Timings::time(&mut timings.read, || fetch_url(&url)).map_err(|e| e.to_string())?
             }
-        } else if filename.extension().and_then(OsStr::to_str) == Some("pdf") {
-            /* 
-            PDF support still shows quite some errors and is prone to panic
-            */
-            let bytes = std::fs::read(filename).expect("error opening pdf-file");
-            let text = pdf_extract::extract_text_from_mem(&bytes).expect("error reading pdf-file");
-            let content_vec: Vec<String> = trim_to_words(text);
-            let mut words_near_vec: Vec<String> = Vec::new();
-
-            for (index, word) in content_vec.clone().into_iter().enumerate() {
-                *frequency.entry(word.to_owned()).or_insert(0) += 1;
-
-                let min: usize = get_index_min(&index);
-                let max: usize = get_index_max(&index, &content_vec.len());
-
-                (for (number, value) in content_vec.iter().enumerate().take(max).skip(min) {
-                    if number == index {
+            Input::File(filename) => {
+                let reader = match find_reader(&readers, &filename) {
+                    Some(reader) => reader,
+                    None => continue,
+                };
+                match Timings::time(&mut timings.read, || reader.read(&filename)) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        let warning = FileWarning::from_read_error(filename, &e);
+                        if let Some(run_log) = &run_log {
+                            run_log.log_file(&source, None, Some(&warning))?;
+                        }
+                        warnings.push(warning);
                         continue;
-                    } else {
-                        //println!("{:?}", content_vec[i]);
-                        words_near_vec.push(value.clone()); //pushes -+5 words to vec
                     }
-                });
-
-                words_near_vec_map
-                    .entry(word.to_owned())
-                    .or_insert_with(Vec::new)
-                    .append(&mut words_near_vec);
+                }
+            }
+        };
+        let (suspect, tokenized) = Timings::time(&mut timings.tokenize, || {
+            let words = trim_to_words_with(&text, &tokenizer_options);
+            //flag (and with --drop-suspect skip) text that looks like a failed extraction
+            let suspect = suspect_text(&words, &suspect_thresholds).map(|reason| FileWarning {
+                path: PathBuf::from(&source),
+                kind: WarningKind::SuspectText,
+                message: if drop_suspect { format!("{}; skipped", reason) } else { reason },
+            });
+            if drop_suspect && suspect.is_some() {
+                return (suspect, None);
             }
-        } else if filename.extension().and_then(OsStr::to_str) == Some("docx") {
-            /* 
-            TO DO: Handle *.docx files
-            */
-            continue;
-        } else {
-            continue;
+            if let Some(sentence_lengths) = &mut sentence_lengths {
+                add_sentence_lengths(&text, &tokenizer_options, sentence_lengths);
+            }
+            if let Some(examples) = &mut examples {
+                examples.add(&text, &source, &tokenizer_options);
+            }
+            if let Some(surface_forms) = &mut surface_forms {
+                add_surface_forms(&text, &tokenizer_options, surface_forms);
+            }
+            let words = match &exclude_pattern {
+                Some(pattern) => {
+                    let before = words.len();
+                    let words = exclude_matching(words, pattern);
+                    excluded_words += before - words.len();
+                    words
+                }
+                None => words,
+            };
+            (suspect, Some(words))
+        });
+        if let Some(run_log) = &run_log {
+            run_log.log_file(&source, tokenized.as_ref().map(Vec::len), suspect.as_ref())?;
         }
+        warnings.extend(suspect);
+        let content_vec: Vec<String> = match tokenized {
+            Some(content_vec) => content_vec,
+            None => continue,
+        };
+        document_lengths.push(content_vec.len());
+        Timings::time(&mut timings.count, || {
+            match context_window {
+                Some(window) => count_words_near_within(
+                    &content_vec,
+                    &mut frequency,
+                    &mut words_near_vec_map,
+                    window,
+                ),
+                None => count_words_near(&content_vec, &mut frequency, &mut words_near_vec_map),
+            }
+            if let Some(vocabulary_growth) = &mut vocabulary_growth {
+                vocabulary_growth.add(&content_vec);
+            }
+        });
     }
 
-    //count Vec with words nears each words
-    for (word, words) in words_near_vec_map {
-        let counted_near = sort_map_to_vec(count_words(&words));
-        map_near.entry(word).or_insert(counted_near);
+    let (counted, keyness_vs_reference) = Timings::time(&mut timings.sort, || {
+        //merge plural and simple morphological variants, using the counts of the whole corpus
+        if light_normalize {
+            let min_count = light_normalize_min_count.unwrap_or(LIGHT_NORMALIZE_MIN_COUNT);
+            let remap = light_normalize_map(&frequency, min_count);
+            apply_light_normalization(&remap, &mut frequency, &mut words_near_vec_map);
+        }
+
+        //compare frequencies against reference corpus
+        let keyness_vs_reference = reference.as_ref().map(|(reference, total)| {
+            let keyness = keyness(&frequency, &reference.counts, *total);
+            match significance {
+                Some(threshold) => {
+                    let correction = correction.unwrap_or_default();
+                    let (significant, removed) = significant_keyness(keyness, threshold, correction);
+                    let note = format!(
+                        "Removed {} words that are not significant at {} ({:?})",
+                        removed, threshold, correction
+                    );
+                    if !report {
+                        println!("{}", note);
+                    }
+                    (significant, Some(note))
+                }
+                None => (keyness, None),
+            }
+        });
+
+        //count Vec with words nears each words
+        for (word, words) in words_near_vec_map {
+            let counted_near = sort_map_to_vec(count_words(&words));
+            map_near.entry(word).or_insert(counted_near);
+        }
+
+        //Sort frequency HashMap into Vec
+        (sort_map_to_vec(frequency), keyness_vs_reference)
+    });
+
+    //all results files of this run start with the same timestamp
+    let timestamp = timestamp(timestamp_kind);
+    //results files saved so far, named if saving a later one fails
+    let mut saved: Vec<PathBuf> = Vec::new();
+    let write_files = || -> Result<PathBuf, Box<dyn std::error::Error>> {
+        //saves [date/time]_`name`.txt in path_dir
+        let mut save = |to_file: String, name: &str| -> std::io::Result<PathBuf> {
+            let to_file = with_newlines(to_file, newline);
+            let filename = save_file_stamped(to_file, path_dir.clone(), name, &timestamp)?;
+            saved.push(filename.clone());
+            Ok(filename)
+        };
+
+        //the rarest words, examples and summary keep their frequency order; the results, keyness and surfaces files are sorted by --sort
+        let mut sorted = counted.clone();
+        sort_for_export(&mut sorted, export_sort);
+        for words_near in map_near.values_mut() {
+            sort_for_export(words_near, export_sort);
+        }
+
+        if let Some((mut keyness_vs_reference, note)) = keyness_vs_reference {
+            if export_sort == ExportSort::KeyAsc {
+                keyness_vs_reference.sort_by(|a, b| a.word.cmp(&b.word));
+            }
+            //the words left out by --significance are counted in the first line
+            let mut to_file = note.map(|note| note + "\n\n").unwrap_or_default();
+            to_file.push_str(&format_keyness(&keyness_vs_reference, precision)?);
+            let keyness_filename = save(to_file, "results_word_analysis_keyness_vs_reference")?;
+            println!("Keyness compared to reference corpus saved to {:?}", keyness_filename);
+        }
+
+        if let Some(surface_forms) = &surface_forms {
+            let surfaces_filename = save(
+                format_surface_forms(&sorted, surface_forms, export_sort),
+                "results_word_analysis_surfaces",
+            )?;
+            println!("Surface forms saved to {:?}", surfaces_filename);
+        }
+        if let Some(examples) = &examples {
+            let examples_filename = save(
+                format_examples(examples, &counted, EXAMPLES_TOP_WORDS),
+                "results_word_analysis_examples",
+            )?;
+            println!("Example sentences saved to {:?}", examples_filename);
+        }
+        if let Some(n) = rarest {
+            let rarest_filename =
+                save(format_rarest(&bottom_n(&counted, n)), "results_word_analysis_rare")?;
+            println!("Rarest words saved to {:?}", rarest_filename);
+        }
+        if let Some(sentence_lengths) = &sentence_lengths {
+            let lengths_filename = save(
+                format_sentence_lengths(sentence_lengths, precision)?,
+                "results_word_analysis_sentence_lengths",
+            )?;
+            println!("Sentence lengths saved to {:?}", lengths_filename);
+        }
+        if let Some(vocabulary_growth) = &vocabulary_growth {
+            let growth_filename = save(
+                format_vocabulary_growth(&vocabulary_growth.points()),
+                "results_word_analysis_vocabulary_growth",
+            )?;
+            println!("Vocabulary growth saved to {:?}", growth_filename);
+        }
+
+        //format output and save results to file in analyzed path, format: ("%Y_%m_%d_%H_%M_%S_results_word_analysis.txt")
+        let name = match &url {
+            //results of a URL are saved in the current directory, named after the URL
+            Some(url) => format!("results_word_analysis_{}", url_stem(url)),
+            None => "results_word_analysis".to_string(),
+        };
+        Ok(save(format_results_with(&sorted, &map_near, context_values, precision)?, &name)?)
+    };
+    //printed instead of the results files with --no-files, and when saving them fails
+    let summary = || {
+        let mut summary = format_summary(&counted, SUMMARY_TOP_WORDS);
+        if let Some(n) = rarest {
+            summary.push_str("Rarest words:\n");
+            summary.push_str(&format_rarest(&bottom_n(&counted, n)));
+        }
+        summary
+    };
+
+    //read errors first (also when saving fails below), then the other skipped files
+    warnings.sort_by_key(|warning| warning.kind != WarningKind::Unreadable);
+    for warning in &warnings {
+        eprintln!("{}", warning);
     }
 
-    //Sort frequency HashMap into Vec
-    let counted = sort_map_to_vec(frequency);
+    let filename = if no_files {
+        None
+    } else {
+        match Timings::time(&mut timings.write, write_files) {
+            Ok(filename) => Some(filename),
+            Err(e) => {
+                //the results are computed; show at least the summary and what was saved before failing
+                print!("{}", summary());
+                if saved.is_empty() {
+                    return Err(format!("Saving results failed, only the summary above is available: {}", e).into());
+                }
+                return Err(format!(
+                    "Saving results failed after saving {:?}; the rest is only in the summary above: {}",
+                    saved, e
+                )
+                .into());
+            }
+        }
+    };
 
-    //format output and write to file
-    let mut to_file = String::new();
-    for (word, frequency) in counted {
-        let words_near = &map_near[&word];
-        let combined = format!(
-            "Word: {:?}, Frequency: {:?},\n Words near: {:?}\n\n",
-            word, frequency, words_near
+    //options that probably don't do what was intended
+    let hints: Vec<String> = exclude_pattern
+        .as_ref()
+        .and_then(|pattern| exclude_pattern_hint(pattern.as_str(), excluded_words))
+        .into_iter()
+        .chain(context_window_hint(
+            context_window.unwrap_or(DEFAULT_CONTEXT_WINDOW),
+            &document_lengths,
+        ))
+        .collect();
+    if report {
+        let path = url.unwrap_or_else(|| path.display().to_string());
+        let files = document_lengths.len();
+        print!(
+            "{}",
+            format_run_report(&path, files, &counted, SUMMARY_TOP_WORDS, &warnings, &hints, &timings)
         );
-        to_file.push_str(&combined);
+        return Ok(());
+    }
+    if !quiet && !hints.is_empty() {
+        println!("Hints:");
+        for hint in &hints {
+            println!("  {}", hint);
+        }
     }
 
-    //save results to file in analyzed path, format: ("%Y_%m_%d_%H_%M_%S_results_word_analysis.txt")
-    let filename = save_file(to_file, path_dir)?;
-
-    println!(
-        "Finished in {:?}! Please see file {:?} for results",
-        instant.elapsed(), filename
-    );
+    match filename {
+        Some(filename) => println!(
+            "Finished in {:?}! Please see file {:?} for results",
+            instant.elapsed(),
+            filename
+        ),
+        None => println!(
+            "{}Finished in {:?}! No files saved (--no-files)",
+            summary(),
+            instant.elapsed()
+        ),
+    }
+    if verbose {
+        println!("Time per stage: {}", timings);
+    }
     Ok(())
 }
@@ -0,0 +1,221 @@
+//! Text extraction from office documents (*.docx and *.odt). Both are zip archives with the text in an XML part.
+
+use std::io::{Cursor, Read};
+
+use quick_xml::events::Event;
+use zip::result::ZipError;
+
+use crate::ReadError;
+
+///Extract the text of a *.docx file (word/document.xml). Each paragraph ends with a blank line.
+///Deleted text of tracked changes (`<w:del>`) is skipped; comments are stored in a separate part and are not read.
+pub fn extract_text_from_docx(bytes: &[u8]) -> Result<String, ReadError> {
+    extract_text_from_docx_with(bytes, false)
+}
+
+///Like `extract_text_from_docx`, but with `tracked_changes` the deleted text of tracked changes (`<w:delText>`) is kept
+///and the comments (word/comments.xml) are appended after the text of the document.
+pub fn extract_text_from_docx_with(bytes: &[u8], tracked_changes: bool) -> Result<String, ReadError> {
+    let xml = read_zip_part(bytes, "word/document.xml")?;
+    let whitespace: &[&[u8]] = &[b"w:tab", b"w:br", b"w:cr"];
+    if !tracked_changes {
+        return parse_xml(&xml, &[b"w:t"], &[b"w:p"], whitespace, &[b"w:del"]);
+    }
+    let mut text = parse_xml(&xml, &[b"w:t", b"w:delText"], &[b"w:p"], whitespace, &[])?;
+    if let Some(comments) = read_optional_zip_part(bytes, "word/comments.xml")? {
+        text.push_str(&parse_xml(&comments, &[b"w:t"], &[b"w:p"], whitespace, &[])?);
+    }
+    Ok(text)
+}
+
+///Extract the text of a *.odt file (content.xml). Each paragraph and heading ends with a blank line.
+///Comments (`<office:annotation>`) and deleted text of tracked changes (`<text:tracked-changes>`) are skipped.
+pub fn extract_text_from_odt(bytes: &[u8]) -> Result<String, ReadError> {
+    extract_text_from_odt_with(bytes, false)
+}
+
+///Like `extract_text_from_odt`, but with `tracked_changes` the comments and the deleted text of tracked changes are kept
+///where they are stored in the document (deleted text at the start, comments where they are anchored).
+///Their authors and dates are skipped.
+pub fn extract_text_from_odt_with(bytes: &[u8], tracked_changes: bool) -> Result<String, ReadError> {
+    let xml = read_zip_part(bytes, "content.xml")?;
+    let skip: &[&[u8]] = if tracked_changes {
+        &[b"dc:creator", b"dc:date", b"office:change-info"]
+    } else {
+        &[b"office:annotation", b"text:tracked-changes"]
+    };
+    parse_xml(
+        &xml,
+        &[b"office:body"],
+        &[b"text:p", b"text:h"],
+        &[b"text:s", b"text:tab", b"text:line-break"],
+        skip,
+    )
+}
+
+//reads a single file from the zip archive
+fn read_zip_part(bytes: &[u8], name: &str) -> Result<String, ReadError> {
+    read_optional_zip_part(bytes, name)?
+        .ok_or_else(|| ReadError::Format(format!("{}: {}", name, ZipError::FileNotFound)))
+}
+
+//like `read_zip_part`, but a file that is not in the archive is None
+fn read_optional_zip_part(bytes: &[u8], name: &str) -> Result<Option<String>, ReadError> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| ReadError::Format(e.to_string()))?;
+    let mut part = match archive.by_name(name) {
+        Ok(part) => part,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(ReadError::Format(format!("{}: {}", name, e))),
+    };
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)?;
+    Ok(Some(xml))
+}
+
+//a blank line, so `segment_sentences` ends a sentence at every paragraph, e.g. after a heading without a period
+const PARAGRAPH_END: &str = "\n\n";
+
+//collects text inside `text_tags`, ending paragraphs (also nested ones) with a single blank line and turning `whitespace` elements into a space.
+//Everything inside `skip` elements is ignored.
+fn parse_xml(
+    xml: &str,
+    text_tags: &[&[u8]],
+    paragraphs: &[&[u8]],
+    whitespace: &[&[u8]],
+    skip: &[&[u8]],
+) -> Result<String, ReadError> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut text = String::new();
+    let mut in_text = 0;
+    let mut skipping = 0;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.name();
+                if skip.contains(&name.as_ref()) {
+                    skipping += 1;
+                } else if text_tags.contains(&name.as_ref()) {
+                    in_text += 1;
+                } else if skipping == 0
+                    && paragraphs.contains(&name.as_ref())
+                    && !text.is_empty()
+                    && !text.ends_with('\n')
+                {
+                    //a paragraph inside a paragraph, e.g. a comment: keep it apart from the words before it
+                    text.push_str(PARAGRAPH_END);
+                }
+            }
+            Ok(Event::End(e)) => {
+                let name = e.name();
+                if skip.contains(&name.as_ref()) {
+                    skipping -= 1;
+                } else if text_tags.contains(&name.as_ref()) {
+                    in_text -= 1;
+                } else if skipping == 0
+                    && paragraphs.contains(&name.as_ref())
+                    && !text.ends_with(PARAGRAPH_END)
+                {
+                    text.push_str(PARAGRAPH_END);
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                let name = e.name();
+                if skipping == 0 && whitespace.contains(&name.as_ref()) {
+                    text.push(' ');
+                } else if skipping == 0 && paragraphs.contains(&name.as_ref()) {
+                    text.push_str(PARAGRAPH_END);
+                }
+            }
+            Ok(Event::Text(e)) if in_text > 0 && skipping == 0 => {
+                let unescaped = e.unescape().map_err(|e| ReadError::Format(e.to_string()))?;
+                text.push_str(&unescaped);
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(ReadError::Format(e.to_string())),
+            _ => (),
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    fn zip_with(name: &str, xml: &str) -> Vec<u8> {
+        zip_with_parts(&[(name, xml)])
+    }
+
+    fn zip_with_parts(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, xml) in parts {
+            archive.start_file(*name, FileOptions::default()).unwrap();
+            archive.write_all(xml.as_bytes()).unwrap();
+        }
+        archive.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_docx() {
+        let docx = zip_with(
+            "word/document.xml",
+            r#"<w:document><w:body><w:p><w:r><w:t xml:space="preserve">Kept &amp; </w:t></w:r><w:del w:author="A"><w:r><w:delText>deleted</w:delText></w:r></w:del><w:r><w:t>text</w:t></w:r></w:p><w:p><w:r><w:t>Second</w:t><w:br/><w:t>line</w:t></w:r></w:p></w:body></w:document>"#,
+        );
+        assert_eq!(extract_text_from_docx(&docx).unwrap(), "Kept & text\n\nSecond line\n\n");
+        assert!(extract_text_from_docx(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_docx_heading_is_a_sentence() {
+        let docx = zip_with(
+            "word/document.xml",
+            r#"<w:document><w:body><w:p><w:r><w:t>Heading</w:t></w:r></w:p><w:p><w:r><w:t>First sentence here.</w:t></w:r></w:p></w:body></w:document>"#,
+        );
+        let text = extract_text_from_docx(&docx).unwrap();
+        let sentences: Vec<&str> = crate::segment_sentences(&text, &crate::TokenizerOptions::default())
+            .iter()
+            .map(|span| &text[span.byte_start..span.byte_end])
+            .collect();
+        assert_eq!(sentences, vec!["Heading", "First sentence here."]);
+    }
+
+    #[test]
+    fn test_odt() {
+        let odt = zip_with(
+            "content.xml",
+            r#"<office:document-content><office:body><office:text><text:tracked-changes><text:changed-region><text:deletion><text:p>deleted</text:p></text:deletion></text:changed-region></text:tracked-changes><text:h>Title</text:h><text:p>One<text:s/>two<office:annotation><text:p>comment</text:p></office:annotation></text:p></office:text></office:body></office:document-content>"#,
+        );
+        assert_eq!(extract_text_from_odt(&odt).unwrap(), "Title\n\nOne two\n\n");
+    }
+
+    #[test]
+    fn test_docx_tracked_changes() {
+        let document = r#"<w:document><w:body><w:p><w:r><w:t xml:space="preserve">Kept </w:t></w:r><w:del w:author="A"><w:r><w:delText xml:space="preserve">deleted </w:delText></w:r></w:del><w:ins w:author="A"><w:r><w:t>text</w:t></w:r></w:ins></w:p></w:body></w:document>"#;
+        let comments = r#"<w:comments><w:comment w:author="A"><w:p><w:r><w:t>A comment</w:t></w:r></w:p></w:comment></w:comments>"#;
+        let docx = zip_with_parts(&[("word/document.xml", document), ("word/comments.xml", comments)]);
+        assert_eq!(extract_text_from_docx_with(&docx, false).unwrap(), "Kept text\n\n");
+        assert_eq!(
+            extract_text_from_docx_with(&docx, true).unwrap(),
+            "Kept deleted text\n\nA comment\n\n"
+        );
+        //a document without comments
+        let docx = zip_with("word/document.xml", document);
+        assert_eq!(extract_text_from_docx_with(&docx, true).unwrap(), "Kept deleted text\n\n");
+    }
+
+    #[test]
+    fn test_odt_tracked_changes() {
+        let odt = zip_with(
+            "content.xml",
+            r#"<office:document-content><office:body><office:text><text:tracked-changes><text:changed-region><text:deletion><office:change-info><dc:creator>A</dc:creator><dc:date>2024-05-01</dc:date></office:change-info><text:p>deleted</text:p></text:deletion></text:changed-region></text:tracked-changes><text:h>Title</text:h><text:p>One<text:s/>two<office:annotation><dc:creator>B</dc:creator><text:p>comment</text:p></office:annotation></text:p></office:text></office:body></office:document-content>"#,
+        );
+        assert_eq!(extract_text_from_odt_with(&odt, false).unwrap(), "Title\n\nOne two\n\n");
+        assert_eq!(
+            extract_text_from_odt_with(&odt, true).unwrap(),
+            "deleted\n\nTitle\n\nOne two\n\ncomment\n\n"
+        );
+    }
+}
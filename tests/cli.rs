@@ -0,0 +1,244 @@
+//runs the text_analysis binary and checks what it prints and how it exits
+
+use std::fs;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+//a new empty directory for one test, removed when the test ends (also if it panics)
+struct TestDir(PathBuf);
+
+impl Deref for TestDir {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn test_dir(name: &str) -> TestDir {
+    let dir = std::env::temp_dir()
+        .join(format!("text_analysis_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TestDir(dir)
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_text_analysis"))
+        .args(args)
+        .output()
+        .expect("failed to run text_analysis")
+}
+
+//runs with the results file timestamp fixed to 1970-01-01 00:00:00 UTC
+fn run_at_epoch(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_text_analysis"))
+        .args(args)
+        .env("SOURCE_DATE_EPOCH", "0")
+        .output()
+        .expect("failed to run text_analysis")
+}
+
+#[test]
+fn test_warnings_are_printed_when_saving_fails() {
+    let dir = test_dir("save_fails");
+    fs::write(dir.join("a.txt"), "The cat sat on the mat. The dog sat too.").unwrap();
+    fs::write(dir.join("b.txt"), [b'c', b'a', b't', 0xff, 0xfe]).unwrap();
+    //a directory where the results file would go makes saving fail after the writable check,
+    //and after the rarest words file is saved
+    fs::create_dir(dir.join("1970_01_01_00_00_00_000Z_results_word_analysis.txt")).unwrap();
+
+    let output = run_at_epoch(&[
+        dir.to_str().unwrap(),
+        "--invalid-utf8",
+        "skip",
+        "--timestamp",
+        "utc-ms",
+        "--rarest",
+        "2",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stdout.contains("\"sat\""), "summary missing from {:?}", stdout);
+    assert!(
+        stderr.contains("Skipped") && stderr.contains("b.txt"),
+        "warning missing from {:?}",
+        stderr
+    );
+    assert!(stderr.contains("Saving results failed"), "{:?}", stderr);
+    assert!(
+        stderr.contains("1970_01_01_00_00_00_000Z_results_word_analysis_rare.txt"),
+        "saved file not named in {:?}",
+        stderr
+    );
+    assert!(dir.join("1970_01_01_00_00_00_000Z_results_word_analysis_rare.txt").is_file());
+}
+
+//files can't be created in /proc, and /proc/version is not a supported file: the writable check has to
+//fail first, before the file is looked at
+#[cfg(target_os = "linux")]
+#[test]
+fn test_unwritable_directory_fails_before_reading() {
+    let output = run(&["/proc/version"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{:?}", stderr);
+    assert!(stderr.contains("Cannot save results in"), "{:?}", stderr);
+    assert!(!stdout.contains("Finished"), "{:?}", stdout);
+}
+
+#[test]
+fn test_exit_codes_for_nothing_to_analyze() {
+    let dir = test_dir("exit_codes");
+
+    let output = run(&[dir.join("missing").to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(3));
+
+    fs::write(dir.join("notes.text"), "The cat sat on the mat.").unwrap();
+    let output = run(&[dir.join("notes.text").to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(".txt"));
+
+    let empty = dir.join("empty");
+    fs::create_dir(&empty).unwrap();
+    let output = run(&[empty.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(5));
+
+}
+
+#[test]
+fn test_significance_options() {
+    let dir = test_dir("significance");
+    fs::write(dir.join("a.txt"), "cat cat cat cat cat cat cat cat dog the the the").unwrap();
+    fs::write(dir.join("reference.csv"), "the,1000\ndog,10\ncat,1\n__TOTAL__,5000\n").unwrap();
+    let reference = dir.join("reference.csv");
+    let input = dir.join("a.txt");
+
+    //without a reference corpus there is no keyness to filter
+    let output = run(&[input.to_str().unwrap(), "--significance", "0.01"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("need --reference-freq"));
+    let output = run(&[input.to_str().unwrap(), "--correction", "none"]);
+    assert!(!output.status.success());
+
+    for threshold in ["0", "1.5", "-0.1"] {
+        let output = run(&[
+            input.to_str().unwrap(),
+            "--reference-freq",
+            reference.to_str().unwrap(),
+            "--significance",
+            threshold,
+        ]);
+        assert!(!output.status.success(), "{} was accepted", threshold);
+    }
+
+    let output = run(&[
+        input.to_str().unwrap(),
+        "--reference-freq",
+        reference.to_str().unwrap(),
+        "--significance",
+        "0.01",
+        "--timestamp",
+        "run-id",
+    ]);
+    assert!(output.status.success(), "{:?}", String::from_utf8_lossy(&output.stderr));
+    let keyness_file = fs::read_dir(dir.as_path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().ends_with("keyness_vs_reference.txt"))
+        .expect("no keyness file");
+    let keyness = fs::read_to_string(keyness_file).unwrap();
+    assert!(keyness.starts_with("Removed "), "{:?}", keyness);
+    assert!(keyness.contains("not significant at 0.01 (BenjaminiHochberg)\n\nWord: "), "{:?}", keyness);
+}
+
+#[test]
+fn test_empty_reference_corpus_fails_before_reading() {
+    let dir = test_dir("empty_reference");
+    fs::write(dir.join("reference.csv"), "word,count\n").unwrap();
+    let reference = dir.join("reference.csv");
+    //a missing input would exit with 3 if it were looked at first
+    let output = run(&[
+        dir.join("missing").to_str().unwrap(),
+        "--reference-freq",
+        reference.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("0 words in total"));
+
+    fs::write(dir.join("reference.csv"), "cat,3\n").unwrap();
+    let output = run(&[
+        dir.join("missing").to_str().unwrap(),
+        "--reference-freq",
+        reference.to_str().unwrap(),
+        "--reference-total",
+        "0",
+    ]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("0 words in total"));
+}
+
+#[test]
+fn test_rarest_words_are_printed_without_files() {
+    let dir = test_dir("rarest");
+    fs::write(dir.join("a.txt"), "the cat sat on the mat, teh cat").unwrap();
+    let output = run(&[dir.to_str().unwrap(), "--no-files", "--rarest", "2"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Rarest words:\nWord: \"mat\", Frequency: 1\nWord: \"on\", Frequency: 1\n"),
+        "{:?}",
+        stdout
+    );
+    assert_eq!(fs::read_dir(dir.as_path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_report_prints_json_without_saving_files() {
+    let dir = test_dir("report");
+    fs::write(dir.join("a.txt"), "The cat sat on the mat. The dog sat too.").unwrap();
+
+    let output = run(&["report", dir.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{:?}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.starts_with("{\"path\":"), "{:?}", stdout);
+    assert!(stdout.contains("\"files\":1,\"words\":10,\"distinct_words\":7"), "{:?}", stdout);
+    assert!(stdout.contains("{\"word\":\"the\",\"count\":3}"), "{:?}", stdout);
+    assert_eq!(stdout.lines().count(), 1, "{:?}", stdout);
+    assert_eq!(fs::read_dir(dir.as_path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_merge_results_files() {
+    let dir = test_dir("merge");
+    fs::write(
+        dir.join("first.txt"),
+        "Word: \"the\", Frequency: 3,\n Words near: [(\"cat\", 1)]\n\nWord: \"cat\", Frequency: 1,\n Words near: []\n\n",
+    )
+    .unwrap();
+    fs::write(dir.join("second.csv"), "word,count\nCat,2\n__TOTAL__,10\n").unwrap();
+    let merged = dir.join("merged.csv");
+
+    let output = run(&[
+        "merge",
+        dir.join("first.txt").to_str().unwrap(),
+        dir.join("second.csv").to_str().unwrap(),
+        "--output",
+        merged.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        fs::read_to_string(&merged).unwrap(),
+        "word,count\ncat,3\nthe,3\n__TOTAL__,14\n"
+    );
+
+    let output = run(&["merge"]);
+    assert!(!output.status.success());
+}